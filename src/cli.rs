@@ -1,4 +1,9 @@
-use clap::{Args, Parser, Subcommand};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::duration;
 
 #[derive(Parser)]
 #[command(author, version, about="A Development ENVironment managER", long_about=None)]
@@ -7,14 +12,21 @@ pub struct Cli {
     #[clap(subcommand)]
     pub command: Commands,
 
-    // Config file to use
+    // Config file to use, the default one is optional in a project
     #[arg(
         short,
         long,
-        default_value = "~/.config/denver/config.yml",
-        help = "The path to the configuration file to be used"
+        help = "The path to the configuration file to be used [default: ~/.config/denver/config.yml]"
     )]
-    pub config: String,
+    pub config: Option<String>,
+
+    // Profile to apply over the configuration
+    #[arg(
+        long,
+        env = "DENVER_PROFILE",
+        help = "A profile from the configuration file to be applied"
+    )]
+    pub profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -25,12 +37,28 @@ pub enum Commands {
     Build(Build),
     #[command(about = "List containers managed by denver")]
     Status(Status),
+    #[command(about = "Start an existing container without recreating it")]
+    Start(Start),
+    #[command(about = "Stop and start an existing container")]
+    Restart(Restart),
     #[command(about = "Stop running containers")]
     Stop(Stop),
+    #[command(about = "Remove containers managed by denver")]
+    Rm(Rm),
+    #[command(about = "Stop and remove everything denver created")]
+    Down(Down),
     #[command(about = "Generate auto-completions")]
     Completion(Completion),
     #[command(about = "Rebuild containers when changing its context")]
     Watch(Run),
+    #[command(about = "Run a command inside a running development container")]
+    Exec(Exec),
+    #[command(about = "Show the output of a running container")]
+    Logs(Logs),
+    #[command(about = "Manage networks created by denver")]
+    Network(Network),
+    #[command(about = "Inspect the configuration file")]
+    Config(ConfigArgs),
 }
 
 #[derive(Args)]
@@ -62,6 +90,19 @@ pub struct Run {
     // If set, doesn't rebuild the image
     #[arg(long, help = "Run the container without rebuilding its image")]
     pub no_rebuild: bool,
+
+    // If set, waits for healthchecks to pass
+    #[arg(
+        long,
+        help = "Wait for containers with a healthcheck to become healthy before moving on"
+    )]
+    pub wait: bool,
+
+    #[arg(
+        long,
+        help = "Leave running containers alone if their configuration and image didn't change"
+    )]
+    pub if_changed: bool,
 }
 
 #[derive(Args)]
@@ -71,6 +112,83 @@ pub struct Status {
         help = "List only containers matching this pattern"
     )]
     pub pattern: String,
+
+    #[arg(
+        short,
+        long,
+        default_value = "table",
+        value_parser = parse_status_format,
+        help = "Output format: table, json, yaml or a template like '{{.Name}} {{.State}}'"
+    )]
+    pub format: StatusFormat,
+
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "id,name,image,state,status,ports",
+        help = "Comma separated list of columns to show"
+    )]
+    pub columns: Vec<Column>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Column {
+    Id,
+    Name,
+    Image,
+    State,
+    Status,
+    Ports,
+    Created,
+    Uptime,
+    // Whether the container runs an older image than the one tagged
+    Stale,
+    // What changed since the container was created, if anything
+    Outdated,
+    Cpu,
+    Memory,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StatusFormat {
+    Table,
+    Json,
+    Yaml,
+    Template(String),
+}
+
+fn parse_status_format(format: &str) -> Result<StatusFormat, String> {
+    match format {
+        "table" => Ok(StatusFormat::Table),
+        "json" => Ok(StatusFormat::Json),
+        "yaml" => Ok(StatusFormat::Yaml),
+        template if template.contains("{{") => Ok(StatusFormat::Template(template.to_string())),
+        format => Err(format!(
+            "Unknown format '{}', expected table, json, yaml or a template",
+            format
+        )),
+    }
+}
+
+#[derive(Args)]
+pub struct Start {
+    #[arg(help = "The name of an existing container")]
+    pub container: String,
+}
+
+#[derive(Args)]
+pub struct Restart {
+    #[arg(help = "The name of an existing container")]
+    pub container: String,
+
+    #[arg(
+        short,
+        long,
+        value_parser = duration::parse_secs,
+        help = "Time to wait for the container to stop before killing it [default: its stop_timeout or 5s]"
+    )]
+    pub timeout: Option<Duration>,
 }
 
 #[derive(Args)]
@@ -80,6 +198,167 @@ pub struct Stop {
         help = "Stop only containers matching this pattern"
     )]
     pub pattern: String,
+
+    #[arg(long, help = "Remove the containers once stopped")]
+    pub rm: bool,
+
+    #[arg(
+        short,
+        long,
+        value_parser = duration::parse_secs,
+        help = "Time to wait for containers to stop before killing them [default: their stop_timeout or 5s]"
+    )]
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Args)]
+pub struct Rm {
+    #[arg(
+        default_value = ".*",
+        help = "Remove only containers matching this pattern"
+    )]
+    pub pattern: String,
+
+    #[arg(short, long, help = "Remove running containers too")]
+    pub force: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Remove anonymous volumes attached to the containers"
+    )]
+    pub volumes: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Remove the containers without asking for confirmation"
+    )]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct Down {
+    #[arg(
+        long,
+        help = "Remove the images of the containers in the configuration"
+    )]
+    pub images: bool,
+
+    #[arg(long, help = "Remove networks created by denver")]
+    pub networks: bool,
+
+    #[arg(
+        long,
+        help = "Remove named volumes used by the containers in the configuration, including ones denver did not create"
+    )]
+    pub volumes: bool,
+
+    #[arg(long, help = "Print what would be removed without removing anything")]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct Exec {
+    // Container to run the command in
+    #[arg(help = "The name assigned to the container in the configuration file")]
+    pub container: String,
+
+    // Overrides the configured workspace
+    #[arg(
+        short,
+        long,
+        help = "Directory to run the command in, defaults to the container workspace"
+    )]
+    pub workdir: Option<String>,
+
+    // Command to be run, a shell by default
+    #[arg(
+        default_value = "/bin/sh",
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        help = "The command to run inside the container"
+    )]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct Logs {
+    // Container to read the output from
+    #[arg(help = "The name assigned to the container in the configuration file")]
+    pub container: String,
+
+    #[arg(short, long, help = "Keep streaming output as it is generated")]
+    pub follow: bool,
+
+    #[arg(long, help = "Number of lines to show from the end of the logs")]
+    pub tail: Option<u64>,
+
+    #[arg(
+        long,
+        value_parser = parse_since,
+        help = "Show logs since a timestamp (e.g. 2022-10-01T12:00:00Z) or a relative duration (e.g. 10m)"
+    )]
+    pub since: Option<DateTime<Utc>>,
+
+    #[arg(short, long, help = "Show timestamps")]
+    pub timestamps: bool,
+}
+
+fn parse_since(since: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(since) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    let elapsed = duration::parse(since)?;
+    match SystemTime::now().checked_sub(elapsed) {
+        Some(since) => Ok(since.into()),
+        None => Err(format!("Invalid duration: {}", since)),
+    }
+}
+
+#[derive(Args)]
+pub struct Network {
+    #[clap(subcommand)]
+    pub command: NetworkCommands,
+}
+
+#[derive(Subcommand)]
+pub enum NetworkCommands {
+    #[command(about = "Remove networks created by denver that have no containers")]
+    Prune,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[clap(subcommand)]
+    pub command: ConfigCommands,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    #[command(about = "Check the configuration for problems without running anything")]
+    Validate,
+    #[command(about = "Print the fully resolved configuration")]
+    Show(Show),
+}
+
+#[derive(Clone, ValueEnum)]
+pub enum ConfigFormat {
+    Yaml,
+    Json,
+}
+
+#[derive(Args)]
+pub struct Show {
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value_t = ConfigFormat::Yaml,
+        help = "Output format"
+    )]
+    pub format: ConfigFormat,
 }
 
 #[derive(Args)]
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug, Default)]
+#[serde(rename = "args", deny_unknown_fields)]
+pub struct RunArgs {
+    #[serde(default)]
+    pub interactive: bool,
+    #[serde(default)]
+    pub tty: bool,
+    #[serde(default)]
+    pub auto_remove: bool,
+    #[serde(default)]
+    pub privileged: bool,
+    #[serde(default)]
+    pub init: bool,
+    #[serde(default)]
+    pub read_only: bool,
+    pub cap_add: Option<Vec<String>>,
+    pub cap_drop: Option<Vec<String>>,
+    pub security_opt: Option<Vec<String>>,
+    pub devices: Option<Vec<String>>,
+    pub user: Option<String>,
+    pub hostname: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub shm_size: Option<u64>,
+    pub ulimits: Option<HashMap<String, Ulimit>>,
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum Ulimit {
+    Single(i64),
+    Range { soft: i64, hard: i64 },
+}
+
+impl Ulimit {
+    pub fn soft(&self) -> i64 {
+        match self {
+            Ulimit::Single(limit) => *limit,
+            Ulimit::Range { soft, .. } => *soft,
+        }
+    }
+
+    pub fn hard(&self) -> i64 {
+        match self {
+            Ulimit::Single(limit) => *limit,
+            Ulimit::Range { hard, .. } => *hard,
+        }
+    }
+}
+
+// Parses docker style sizes, i.e. 64m or 1g, bare numbers are bytes
+fn parse_size(size: &str) -> Result<u64, String> {
+    let size = size.trim().to_lowercase();
+    let (value, multiplier) = match size.char_indices().last() {
+        Some((i, 'b')) => (&size[..i], 1),
+        Some((i, 'k')) => (&size[..i], 1 << 10),
+        Some((i, 'm')) => (&size[..i], 1 << 20),
+        Some((i, 'g')) => (&size[..i], 1 << 30),
+        _ => (size.as_str(), 1),
+    };
+
+    match value.parse::<u64>() {
+        Ok(value) => value
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("Size too large: {}", size)),
+        Err(_) => Err(format!("Invalid size: {}", size)),
+    }
+}
+
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match Option::<Size>::deserialize(deserializer)? {
+        Some(Size::Bytes(size)) => Ok(Some(size)),
+        Some(Size::Text(size)) => parse_size(&size).map(Some).map_err(de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+// Flags accepted by the old list form of `args`
+const FLAGS: &[&str] = &[
+    "i",
+    "interactive",
+    "t",
+    "tty",
+    "rm",
+    "privileged",
+    "init",
+    "read_only",
+];
+
+// `args` used to be a list of docker run flags, this keeps those
+// configurations working while rejecting flags that were silently ignored.
+pub fn deserialize<'de, D>(deserializer: D) -> Result<RunArgs, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct RunArgsVisitor;
+
+    impl<'de> Visitor<'de> for RunArgsVisitor {
+        type Value = RunArgs;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a map of run options or a list of flags")
+        }
+
+        fn visit_none<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(RunArgs::default())
+        }
+
+        fn visit_unit<E>(self) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(RunArgs::default())
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: SeqAccess<'de>,
+        {
+            let mut args = RunArgs::default();
+
+            while let Some(flag) = seq.next_element::<String>()? {
+                match flag.as_str() {
+                    // Interactive containers always got a TTY allocated
+                    "i" | "interactive" => {
+                        args.interactive = true;
+                        args.tty = true;
+                    }
+                    "t" | "tty" => args.tty = true,
+                    "rm" => args.auto_remove = true,
+                    "privileged" => args.privileged = true,
+                    "init" => args.init = true,
+                    "read_only" => args.read_only = true,
+                    flag => return Err(de::Error::unknown_variant(flag, FLAGS)),
+                }
+            }
+
+            Ok(args)
+        }
+
+        fn visit_map<A>(self, map: A) -> Result<Self::Value, A::Error>
+        where
+            A: MapAccess<'de>,
+        {
+            RunArgs::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+
+    deserializer.deserialize_any(RunArgsVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<RunArgs, serde_yaml::Error> {
+        deserialize(serde_yaml::Deserializer::from_str(args))
+    }
+
+    #[test]
+    fn test_legacy_flags() {
+        let args = parse("[i, rm, privileged]").unwrap();
+
+        assert!(args.interactive);
+        assert!(args.tty);
+        assert!(args.auto_remove);
+        assert!(args.privileged);
+        assert!(!args.init);
+
+        assert_eq!(parse("~").unwrap(), RunArgs::default());
+
+        let error = parse("[i, detach]").unwrap_err().to_string();
+        assert!(error.contains("unknown variant `detach`"));
+    }
+
+    #[test]
+    fn test_typed() {
+        let args = parse(
+            r#"
+interactive: true
+cap_add: [SYS_PTRACE]
+shm_size: 64m
+ulimits:
+    nofile:
+        soft: 1024
+        hard: 4096
+    nproc: 512
+"#,
+        )
+        .unwrap();
+
+        assert!(args.interactive);
+        assert!(!args.tty);
+        assert_eq!(args.cap_add, Some(vec!["SYS_PTRACE".to_string()]));
+        assert_eq!(args.shm_size, Some(64 * 1024 * 1024));
+
+        let ulimits = args.ulimits.unwrap();
+        assert_eq!(
+            (ulimits["nofile"].soft(), ulimits["nofile"].hard()),
+            (1024, 4096)
+        );
+        assert_eq!(
+            (ulimits["nproc"].soft(), ulimits["nproc"].hard()),
+            (512, 512)
+        );
+
+        let error = parse("detach: true").unwrap_err().to_string();
+        assert!(error.contains("unknown field `detach`"));
+
+        let error = parse("shm_size: 18446744073709551615g")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Size too large"), "{}", error);
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use super::RunConfig;
+
+// Builds the environment for a container as a list of KEY=VALUE strings.
+// Files in `env_file` are loaded in order, with later files overriding
+// earlier ones, and variables in `env` take precedence over all of them.
+pub fn resolve(run: &RunConfig) -> Result<Vec<String>, String> {
+    let mut vars = BTreeMap::new();
+
+    for path in run.env_file.iter().flatten() {
+        let iter = dotenvy::from_path_iter(path)
+            .map_err(|e| format!("Failed to read env file {}: {}", path, e))?;
+
+        for var in iter {
+            let (key, value) =
+                var.map_err(|e| format!("Failed to parse env file {}: {}", path, e))?;
+            vars.insert(key, value);
+        }
+    }
+
+    for (key, value) in run.env.iter().flatten() {
+        vars.insert(key.clone(), value.clone());
+    }
+
+    Ok(vars
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::config::Config;
+
+    #[test]
+    fn test_resolve() {
+        let dir = env::temp_dir().join(format!("denver-env-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let base = dir.join("base.env");
+        let local = dir.join("local.env");
+        std::fs::write(&base, "# base\nA=base\nB=base\nC=base\n").unwrap();
+        std::fs::write(&local, "export B=\"local\"\nC=local\n").unwrap();
+        env::set_var("DENVER_TEST_HOST_VAR", "host");
+
+        let config = format!(
+            r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        env_file: [{}, {}]
+        env:
+            C: config
+            D: ${{DENVER_TEST_HOST_VAR}}-suffix
+            E: ${{DENVER_TEST_UNDEFINED_VAR:-}}
+    tag: quay.io/org/app:tag
+        "#,
+            base.display(),
+            local.display()
+        );
+        let config = Config::new(&config).unwrap();
+
+        let vars = resolve(&config.containers["app"].run).unwrap();
+        assert_eq!(
+            vars,
+            vec!["A=base", "B=local", "C=config", "D=host-suffix", "E="]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_missing_file() {
+        let config = r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        env_file: [/does/not/exist.env]
+    tag: quay.io/org/app:tag
+        "#;
+        let config = Config::new(config).unwrap();
+
+        let error = resolve(&config.containers["app"].run).unwrap_err();
+        assert!(error.starts_with("Failed to read env file /does/not/exist.env"));
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt::Display;
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: String,
+        error: String,
+    },
+    Parse {
+        path: Option<String>,
+        line: Option<usize>,
+        column: Option<usize>,
+        key: Option<String>,
+        error: String,
+    },
+    UnknownProfile {
+        name: String,
+        available: Vec<String>,
+    },
+}
+
+impl ConfigError {
+    pub fn parse(error: serde_path_to_error::Error<serde_yaml::Error>, path: Option<&str>) -> Self {
+        let key = match error.path().to_string() {
+            // No key available, e.g. on syntax errors
+            k if k == "." || k == "?" => None,
+            k => Some(k),
+        };
+        let error = error.into_inner();
+        let location = error.location();
+        let mut message = error.to_string();
+
+        // serde_yaml embeds the path and location in its messages, we report
+        // those on their own.
+        if let Some(location) = &location {
+            let suffix = format!(" at line {} column {}", location.line(), location.column());
+            if let Some(m) = message.strip_suffix(&suffix) {
+                message = m.to_string();
+            }
+        }
+
+        if let Some((prefix, m)) = message.split_once(": ") {
+            if key.as_ref().map_or(false, |k| k.starts_with(prefix)) {
+                message = m.to_string();
+            }
+        }
+
+        ConfigError::Parse {
+            path: path.map(|p| p.to_string()),
+            line: location.as_ref().map(|l| l.line()),
+            column: location.as_ref().map(|l| l.column()),
+            key,
+            error: message,
+        }
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Read { path, error } => {
+                write!(f, "Failed to read configuration file {}: {}", path, error)
+            }
+            ConfigError::Parse {
+                path,
+                line,
+                column,
+                key,
+                error,
+            } => {
+                if let Some(path) = path {
+                    write!(f, "{}:", path)?;
+                }
+
+                if let (Some(line), Some(column)) = (line, column) {
+                    write!(f, "{}:{}:", line, column)?;
+                }
+
+                if path.is_some() || line.is_some() {
+                    write!(f, " ")?;
+                }
+
+                match key {
+                    Some(key) => write!(f, "{}: {}", key, error),
+                    None => write!(f, "{}", error),
+                }
+            }
+            ConfigError::UnknownProfile { name, available } => {
+                write!(f, "Unknown profile {}", name)?;
+
+                if !available.is_empty() {
+                    write!(f, ", available profiles: {}", available.join(", "))?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
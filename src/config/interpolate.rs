@@ -0,0 +1,178 @@
+use std::env;
+
+use serde_yaml::Value;
+
+use super::expand_home;
+
+// Variables provided by denver itself, they take precedence over the host
+// environment. There is no container name outside of containers.
+pub struct Builtins<'a> {
+    pub container_name: Option<&'a str>,
+    pub config_dir: &'a str,
+}
+
+impl Builtins<'_> {
+    fn lookup(&self, name: &str) -> Option<String> {
+        match name {
+            "CONTAINER_NAME" => self.container_name.map(|name| name.to_string()),
+            "CONFIG_DIR" => Some(self.config_dir.to_string()),
+            _ => env::var(name).ok(),
+        }
+    }
+}
+
+// Expands `${VAR}` and `${VAR:-default}` references and a leading `~` in a
+// string, `$$` is a literal `$`. Defaults are used when a variable is unset
+// or empty.
+pub fn interpolate(value: &str, builtins: &Builtins) -> Result<String, String> {
+    let mut result = String::new();
+    let mut rest = expand_home(value);
+
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+
+        if let Some(tail) = tail.strip_prefix('$') {
+            result.push('$');
+            rest = tail.to_string();
+            continue;
+        }
+
+        let tail = match tail.strip_prefix('{') {
+            Some(tail) => tail,
+            None => {
+                result.push('$');
+                rest = tail.to_string();
+                continue;
+            }
+        };
+
+        let (reference, tail) = match tail.split_once('}') {
+            Some(split) => split,
+            None => return Err(format!("Unterminated variable reference in '{}'", value)),
+        };
+
+        let (name, default) = match reference.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (reference, None),
+        };
+
+        let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(format!("Invalid variable name '{}' in '{}'", name, value));
+        }
+
+        match (builtins.lookup(name), default) {
+            (Some(v), Some(default)) if v.is_empty() => result.push_str(default),
+            (Some(v), _) => result.push_str(&v),
+            (None, Some(default)) => result.push_str(default),
+            (None, None) => return Err(format!("Undefined variable {}", name)),
+        }
+
+        rest = tail.to_string();
+    }
+
+    result.push_str(&rest);
+
+    Ok(result)
+}
+
+// Interpolates every string in a YAML value. Errors carry the key of the
+// offending string, starting from key.
+pub fn interpolate_value(
+    value: &mut Value,
+    key: &str,
+    builtins: &Builtins,
+) -> Result<(), (String, String)> {
+    match value {
+        Value::String(s) => *s = interpolate(s, builtins).map_err(|e| (key.to_string(), e))?,
+        Value::Sequence(seq) => {
+            for (i, value) in seq.iter_mut().enumerate() {
+                interpolate_value(value, &format!("{}[{}]", key, i), builtins)?;
+            }
+        }
+        Value::Mapping(map) => {
+            for (k, value) in map.iter_mut() {
+                let key = match k.as_str() {
+                    Some(k) => format!("{}.{}", key, k),
+                    None => key.to_string(),
+                };
+                interpolate_value(value, &key, builtins)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUILTINS: Builtins = Builtins {
+        container_name: Some("app"),
+        config_dir: "/etc/denver",
+    };
+
+    #[test]
+    fn test_interpolate() {
+        env::set_var("DENVER_TEST_INTERPOLATE", "value");
+        env::set_var("DENVER_TEST_EMPTY", "");
+
+        for (input, expected) in [
+            ("plain", "plain"),
+            ("${DENVER_TEST_INTERPOLATE}", "value"),
+            ("a-${DENVER_TEST_INTERPOLATE}-b", "a-value-b"),
+            ("${DENVER_TEST_UNDEFINED:-fallback}", "fallback"),
+            ("${DENVER_TEST_EMPTY:-fallback}", "fallback"),
+            ("${DENVER_TEST_INTERPOLATE:-fallback}", "value"),
+            ("${DENVER_TEST_UNDEFINED:-}", ""),
+            ("${CONFIG_DIR}/${CONTAINER_NAME}", "/etc/denver/app"),
+            ("a~b", "a~b"),
+            ("$$HOME $HOME", "$HOME $HOME"),
+        ] {
+            assert_eq!(
+                interpolate(input, &BUILTINS).unwrap(),
+                expected,
+                "{}",
+                input
+            );
+        }
+
+        assert_eq!(
+            interpolate("~/src", &BUILTINS).unwrap(),
+            format!("{}/src", env::var("HOME").unwrap_or_default())
+        );
+    }
+
+    #[test]
+    fn test_interpolate_errors() {
+        assert_eq!(
+            interpolate("${DENVER_TEST_UNDEFINED}", &BUILTINS).unwrap_err(),
+            "Undefined variable DENVER_TEST_UNDEFINED"
+        );
+        assert!(interpolate("${UNTERMINATED", &BUILTINS).is_err());
+        assert!(interpolate("${1INVALID}", &BUILTINS).is_err());
+
+        let builtins = Builtins {
+            container_name: None,
+            config_dir: "/etc/denver",
+        };
+        assert_eq!(
+            interpolate("${CONTAINER_NAME}", &builtins).unwrap_err(),
+            "Undefined variable CONTAINER_NAME"
+        );
+
+        let mut value: Value =
+            serde_yaml::from_str("run: {volumes: [a, '${DENVER_TEST_UNDEFINED}']}").unwrap();
+        assert_eq!(
+            interpolate_value(&mut value, "containers.app", &BUILTINS).unwrap_err(),
+            (
+                "containers.app.run.volumes[1]".to_string(),
+                "Undefined variable DENVER_TEST_UNDEFINED".to_string()
+            )
+        );
+    }
+}
@@ -0,0 +1,943 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde::de::{DeserializeSeed, IgnoredAny, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use serde_yaml::{Mapping, Value};
+
+use super::interpolate::{interpolate, interpolate_value, Builtins};
+use super::{expand_home, Config, ConfigError, ContainerConfig, Engine, NetworkConfig, TlsConfig};
+
+// A single configuration file. Containers and templates are kept as plain
+// YAML until every file is loaded, since they can extend each other.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Document {
+    include: Option<Vec<String>>,
+    socket: Option<String>,
+    tls: Option<TlsConfig>,
+    engine: Option<Engine>,
+    templates: Option<HashMap<String, Value>>,
+    containers: Option<HashMap<String, Value>>,
+    networks: Option<HashMap<String, NetworkConfig>>,
+    profiles: Option<HashMap<String, Profile>>,
+}
+
+// Settings applied over the rest of the configuration when selected
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Profile {
+    socket: Option<String>,
+    tls: Option<TlsConfig>,
+    engine: Option<Engine>,
+    // Added to the build args of every container
+    build_args: Option<HashMap<String, String>>,
+    // Deep merged over the container with the same name
+    containers: Option<HashMap<String, Value>>,
+}
+
+struct Source {
+    path: Option<String>,
+    // Directory relative paths are taken from, only known for files
+    dir: Option<PathBuf>,
+    text: String,
+}
+
+impl Source {
+    fn config_dir(&self) -> String {
+        match &self.dir {
+            Some(dir) => dir.to_string_lossy().to_string(),
+            None => ".".to_string(),
+        }
+    }
+
+    fn error(&self, key: &str, error: String) -> ConfigError {
+        ConfigError::Parse {
+            path: self.path.clone(),
+            line: None,
+            column: None,
+            key: Some(key.to_string()),
+            error,
+        }
+    }
+
+    // Sockets and certificates are interpolated as well, keys are prefix
+    // followed by the setting.
+    fn interpolate_connection(
+        &self,
+        prefix: &str,
+        socket: &mut Option<String>,
+        tls: &mut Option<TlsConfig>,
+    ) -> Result<(), ConfigError> {
+        let config_dir = self.config_dir();
+        let builtins = Builtins {
+            container_name: None,
+            config_dir: &config_dir,
+        };
+
+        if let Some(socket) = socket {
+            *socket = interpolate(socket, &builtins)
+                .map_err(|e| self.error(&format!("{}socket", prefix), e))?;
+        }
+
+        if let Some(cert_path) = tls.as_mut().and_then(|tls| tls.cert_path.as_mut()) {
+            *cert_path = interpolate(cert_path, &builtins)
+                .map_err(|e| self.error(&format!("{}tls.cert_path", prefix), e))?;
+        }
+
+        Ok(())
+    }
+}
+
+// A container, template or profile, along with where it was defined
+struct Entry<T = Value> {
+    value: T,
+    source: Rc<Source>,
+}
+
+impl<T> Entry<T> {
+    fn error(&self, key: &str, error: String) -> ConfigError {
+        self.source.error(key, error)
+    }
+
+    // Interpolates a value defined in this entry's file and resolves its
+    // relative paths from there. Done before merging, so whatever comes
+    // from a template stays relative to the template's file.
+    fn prepare(&self, value: &mut Value, key: &str, name: &str) -> Result<(), ConfigError> {
+        let config_dir = self.source.config_dir();
+        let builtins = Builtins {
+            container_name: Some(name),
+            config_dir: &config_dir,
+        };
+        interpolate_value(value, key, &builtins).map_err(|(key, error)| self.error(&key, error))?;
+
+        if let Some(dir) = &self.source.dir {
+            resolve_paths(value, dir);
+        }
+
+        Ok(())
+    }
+}
+
+// Collects configuration files, with later ones overriding containers,
+// templates, networks and profiles by name.
+#[derive(Default)]
+pub struct Loader {
+    socket: Option<String>,
+    tls: Option<TlsConfig>,
+    engine: Option<Engine>,
+    templates: HashMap<String, Entry>,
+    containers: HashMap<String, Entry>,
+    networks: Option<HashMap<String, NetworkConfig>>,
+    profiles: HashMap<String, Entry<Profile>>,
+    sources: Vec<String>,
+    // Files currently being loaded, used to detect include cycles
+    stack: Vec<PathBuf>,
+}
+
+impl Loader {
+    pub fn load_file(&mut self, path: &str) -> Result<(), ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(|e| ConfigError::Read {
+            path: path.to_string(),
+            error: e.to_string(),
+        })?;
+
+        let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        self.stack.push(canonical);
+        self.load_str(text, Some(path))?;
+        self.stack.pop();
+
+        self.sources.push(path.to_string());
+
+        Ok(())
+    }
+
+    pub fn load_str(&mut self, text: String, path: Option<&str>) -> Result<(), ConfigError> {
+        let deserializer = serde_yaml::Deserializer::from_str(&text);
+        let mut document: Document = serde_path_to_error::deserialize(deserializer)
+            .map_err(|e| ConfigError::parse(e, path))?;
+
+        let source = Rc::new(Source {
+            path: path.map(|p| p.to_string()),
+            dir: path
+                .and_then(|p| std::fs::canonicalize(p).ok())
+                .and_then(|p| p.parent().map(|d| d.to_path_buf())),
+            text,
+        });
+
+        source.interpolate_connection("", &mut document.socket, &mut document.tls)?;
+        for (name, profile) in document.profiles.iter_mut().flatten() {
+            source.interpolate_connection(
+                &format!("profiles.{}.", name),
+                &mut profile.socket,
+                &mut profile.tls,
+            )?;
+        }
+
+        // Included files are loaded first, so the including one can
+        // override anything they define.
+        for pattern in document.include.iter().flatten() {
+            for include in include_paths(path, pattern)? {
+                self.include(path, &include)?;
+            }
+        }
+
+        // TLS settings go along with the socket they are meant for
+        if document.socket.is_some() {
+            self.socket = document.socket;
+            self.tls = document.tls;
+        } else if document.tls.is_some() {
+            self.tls = document.tls;
+        }
+
+        if document.engine.is_some() {
+            self.engine = document.engine;
+        }
+
+        for (name, value) in document.templates.into_iter().flatten() {
+            let source = source.clone();
+            self.templates.insert(name, Entry { value, source });
+        }
+
+        for (name, value) in document.containers.into_iter().flatten() {
+            let source = source.clone();
+            self.containers.insert(name, Entry { value, source });
+        }
+
+        if let Some(networks) = document.networks {
+            self.networks
+                .get_or_insert_with(HashMap::new)
+                .extend(networks);
+        }
+
+        for (name, value) in document.profiles.into_iter().flatten() {
+            let source = source.clone();
+            self.profiles.insert(name, Entry { value, source });
+        }
+
+        Ok(())
+    }
+
+    fn include(&mut self, parent: Option<&str>, path: &Path) -> Result<(), ConfigError> {
+        let canonical = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+
+            return Err(ConfigError::Parse {
+                path: parent.map(|p| p.to_string()),
+                line: None,
+                column: None,
+                key: Some("include".to_string()),
+                error: format!("Include cycle detected: {}", cycle),
+            });
+        }
+
+        self.load_file(&path.to_string_lossy())
+    }
+
+    pub fn finish(self, profile: Option<&str>) -> Result<Config, ConfigError> {
+        let profile = match profile {
+            Some(name) => Some((name, self.profile(name)?)),
+            None => None,
+        };
+
+        let mut names: Vec<&String> = self.containers.keys().collect();
+        names.sort();
+
+        let mut containers = HashMap::new();
+        for name in names {
+            containers.insert(name.clone(), self.container(name, profile)?);
+        }
+
+        let (socket, tls) = match profile.map(|(_, profile)| &profile.value) {
+            Some(Profile {
+                socket: Some(socket),
+                tls,
+                ..
+            }) => (Some(socket.clone()), tls.clone()),
+            Some(Profile { tls: Some(tls), .. }) => (self.socket.clone(), Some(tls.clone())),
+            _ => (self.socket.clone(), self.tls.clone()),
+        };
+        let engine = profile
+            .and_then(|(_, profile)| profile.value.engine)
+            .or(self.engine)
+            .unwrap_or_default();
+
+        Ok(Config {
+            socket,
+            tls,
+            engine,
+            profile: profile.map(|(name, _)| name.to_string()),
+            containers,
+            networks: self.networks,
+            sources: self.sources,
+        })
+    }
+
+    fn profile(&self, name: &str) -> Result<&Entry<Profile>, ConfigError> {
+        let profile = match self.profiles.get(name) {
+            Some(profile) => profile,
+            None => {
+                let mut available: Vec<String> = self.profiles.keys().cloned().collect();
+                available.sort();
+
+                return Err(ConfigError::UnknownProfile {
+                    name: name.to_string(),
+                    available,
+                });
+            }
+        };
+
+        for container in profile.value.containers.iter().flat_map(|c| c.keys()) {
+            if !self.containers.contains_key(container) {
+                return Err(profile.error(
+                    &format!("profiles.{}.containers.{}", name, container),
+                    format!("no container named {}", container),
+                ));
+            }
+        }
+
+        Ok(profile)
+    }
+
+    fn container(
+        &self,
+        name: &str,
+        profile: Option<(&str, &Entry<Profile>)>,
+    ) -> Result<ContainerConfig, ConfigError> {
+        let entry = &self.containers[name];
+        let key = format!("containers.{}", name);
+        let path = entry.source.path.as_deref();
+
+        let mut value = self.extend(name, &key, entry, &mut vec![key.clone()])?;
+
+        if let Some((profile_name, profile)) = profile {
+            if let Some(build_args) = &profile.value.build_args {
+                let config_dir = profile.source.config_dir();
+                let builtins = Builtins {
+                    container_name: Some(name),
+                    config_dir: &config_dir,
+                };
+                let build_args = build_args
+                    .iter()
+                    .map(|(k, v)| match interpolate(v, &builtins) {
+                        Ok(v) => Ok((k.as_str().into(), v.into())),
+                        Err(e) => Err(profile
+                            .error(&format!("profiles.{}.build_args.{}", profile_name, k), e)),
+                    })
+                    .collect::<Result<Mapping, ConfigError>>()?;
+                let mut build = Mapping::new();
+                build.insert("build_args".into(), build_args.into());
+                let mut overrides = Mapping::new();
+                overrides.insert("build".into(), build.into());
+
+                merge(&mut value, overrides.into());
+            }
+
+            if let Some(overrides) = profile.value.containers.as_ref().and_then(|c| c.get(name)) {
+                let mut overrides = overrides.clone();
+                let key = format!("profiles.{}.containers.{}", profile_name, name);
+                profile.prepare(&mut overrides, &key, name)?;
+                merge(&mut value, overrides);
+            }
+        }
+
+        let mut containers = Mapping::new();
+        containers.insert(name.into(), value);
+        let mut document = Mapping::new();
+        document.insert("containers".into(), containers.into());
+
+        let mut container = match locate(Value::Mapping(document), name) {
+            Ok(container) => container,
+            Err(e) => return Err(self.locate_error(name, ConfigError::parse(e, path))),
+        };
+
+        container.source = entry.source.path.clone();
+
+        Ok(container)
+    }
+
+    // Errors found after merging templates have no location. Containers that
+    // extend nothing are laid out as in their file though, so the error can
+    // be looked up there.
+    fn locate_error(&self, name: &str, mut error: ConfigError) -> ConfigError {
+        let entry = &self.containers[name];
+        let extends = entry
+            .value
+            .as_mapping()
+            .map_or(false, |m| m.contains_key("extends"));
+        if extends {
+            return error;
+        }
+
+        let deserializer = serde_yaml::Deserializer::from_str(&entry.source.text);
+        if let Err(e) = locate(deserializer, name) {
+            let located = ConfigError::parse(e, entry.source.path.as_deref());
+
+            if let (
+                ConfigError::Parse {
+                    key, line, column, ..
+                },
+                ConfigError::Parse {
+                    key: k,
+                    line: l,
+                    column: c,
+                    ..
+                },
+            ) = (&mut error, located)
+            {
+                if *key == k {
+                    *line = l;
+                    *column = c;
+                }
+            }
+        }
+
+        error
+    }
+
+    // Deep merges an entry over whatever it extends, recursively. name is
+    // the container being built.
+    fn extend(
+        &self,
+        name: &str,
+        key: &str,
+        entry: &Entry,
+        chain: &mut Vec<String>,
+    ) -> Result<Value, ConfigError> {
+        let mut value = entry.value.clone();
+
+        let base = match value.as_mapping_mut().and_then(|m| m.remove("extends")) {
+            Some(Value::String(base)) => Some(base),
+            Some(_) => {
+                return Err(entry.error(
+                    &format!("{}.extends", key),
+                    "expected the name of a template or container".to_string(),
+                ))
+            }
+            None => None,
+        };
+
+        entry.prepare(&mut value, key, name)?;
+
+        let base = match base {
+            Some(base) => base,
+            None => return Ok(value),
+        };
+
+        // Templates take precedence over containers with the same name
+        let (base_key, base_entry) = match self.templates.get(&base) {
+            Some(template) => (format!("templates.{}", base), template),
+            None => match self.containers.get(&base) {
+                Some(container) => (format!("containers.{}", base), container),
+                None => {
+                    return Err(entry.error(
+                        &format!("{}.extends", key),
+                        format!("no template or container named {}", base),
+                    ))
+                }
+            },
+        };
+
+        if let Some(start) = chain.iter().position(|k| *k == base_key) {
+            let cycle = chain[start..]
+                .iter()
+                .chain([&base_key])
+                .map(|k| k.as_str())
+                .collect::<Vec<&str>>()
+                .join(" -> ");
+
+            return Err(entry.error(
+                &format!("{}.extends", key),
+                format!("Extends cycle detected: {}", cycle),
+            ));
+        }
+
+        chain.push(base_key.clone());
+        let mut merged = self.extend(name, &base_key, base_entry, chain)?;
+        chain.pop();
+
+        merge(&mut merged, value);
+
+        Ok(merged)
+    }
+}
+
+// Relative paths are taken from dir, the directory of the file the value was
+// defined in.
+fn resolve_paths(value: &mut Value, dir: &Path) {
+    let resolve = |path: &mut String| {
+        if Path::new(path.as_str()).is_relative() {
+            let relative = path.strip_prefix("./").unwrap_or(path);
+            *path = dir.join(relative).to_string_lossy().to_string();
+        }
+    };
+
+    if let Some(Value::String(context)) = field(value, "build", "context") {
+        resolve(context);
+    }
+
+    if let Some(Value::String(workspace)) = field(value, "run", "workspace") {
+        resolve(workspace);
+    }
+
+    if let Some(Value::Sequence(volumes)) = field(value, "run", "volumes") {
+        for volume in volumes {
+            if let Value::String(volume) = volume {
+                // Anything not starting with a dot is a named volume or absolute
+                if volume.starts_with('.') {
+                    let (mut host, rest) = match volume.split_once(':') {
+                        Some((host, rest)) => (host.to_string(), format!(":{}", rest)),
+                        None => (volume.clone(), String::new()),
+                    };
+                    resolve(&mut host);
+                    *volume = host + &rest;
+                }
+            }
+        }
+    }
+
+    if let Some(Value::Sequence(env_files)) = field(value, "run", "env_file") {
+        for path in env_files {
+            if let Value::String(path) = path {
+                resolve(path);
+            }
+        }
+    }
+}
+
+fn field<'a>(value: &'a mut Value, section: &str, name: &str) -> Option<&'a mut Value> {
+    value.get_mut(section)?.get_mut(name)
+}
+
+// Included paths are relative to the including file and can be globs. Plain
+// paths are returned as is, so a missing file is reported as such.
+fn include_paths(parent: Option<&str>, pattern: &str) -> Result<Vec<PathBuf>, ConfigError> {
+    let dir = parent
+        .and_then(|p| Path::new(p).parent())
+        .unwrap_or_else(|| Path::new(""));
+    let pattern = dir.join(expand_home(pattern));
+
+    if !pattern.to_string_lossy().contains(['*', '?', '[']) {
+        return Ok(vec![pattern]);
+    }
+
+    let error = |error: String| ConfigError::Parse {
+        path: parent.map(|p| p.to_string()),
+        line: None,
+        column: None,
+        key: Some("include".to_string()),
+        error,
+    };
+
+    glob::glob(&pattern.to_string_lossy())
+        .map_err(|e| error(e.to_string()))?
+        .map(|path| path.map_err(|e| error(e.to_string())))
+        .collect()
+}
+
+// Mappings are merged key by key, any other value in overrides replaces the
+// one in base.
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Mapping(base), Value::Mapping(overrides)) => {
+            for (key, value) in overrides {
+                match base.get_mut(&key) {
+                    Some(base) => merge(base, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+// Deserializes a single container out of a whole document, ignoring
+// everything else in it. Keeps errors keyed from the document root.
+fn locate<'de, D>(
+    deserializer: D,
+    name: &str,
+) -> Result<ContainerConfig, serde_path_to_error::Error<D::Error>>
+where
+    D: Deserializer<'de>,
+{
+    let mut track = serde_path_to_error::Track::new();
+    let deserializer = serde_path_to_error::Deserializer::new(deserializer, &mut track);
+
+    match Locate(&["containers", name]).deserialize(deserializer) {
+        Ok(container) => Ok(container.expect("container was loaded from this document")),
+        Err(e) => Err(serde_path_to_error::Error::new(track.path(), e)),
+    }
+}
+
+struct Locate<'a>(&'a [&'a str]);
+
+impl<'de> DeserializeSeed<'de> for Locate<'_> {
+    type Value = Option<ContainerConfig>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        if self.0.is_empty() {
+            ContainerConfig::deserialize(deserializer).map(Some)
+        } else {
+            deserializer.deserialize_map(self)
+        }
+    }
+}
+
+impl<'de> Visitor<'de> for Locate<'_> {
+    type Value = Option<ContainerConfig>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut found = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            if key == self.0[0] {
+                found = map.next_value_seed(Locate(&self.0[1..]))?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(documents: &[&str]) -> Result<Config, ConfigError> {
+        let mut loader = Loader::default();
+        for document in documents {
+            loader.load_str(document.to_string(), None)?;
+        }
+        loader.finish(None)
+    }
+
+    #[test]
+    fn test_extends() {
+        let config = load(&[r#"
+templates:
+  base:
+    build:
+        context: ctx/
+        build_args:
+          user: dev
+          shell: bash
+    run:
+        workspace: /some/path/
+        volumes: [/a:/a]
+containers:
+  fedora:
+    extends: base
+    build:
+        build_args:
+          shell: zsh
+    tag: quay.io/org/fedora:tag
+  rawhide:
+    extends: fedora
+    run:
+        volumes: [/b:/b]
+    tag: quay.io/org/rawhide:tag
+"#])
+        .unwrap();
+
+        let fedora = &config.containers["fedora"];
+        let build_args = fedora.build.build_args.as_ref().unwrap();
+        assert_eq!(build_args["user"], "dev");
+        assert_eq!(build_args["shell"], "zsh");
+        assert_eq!(fedora.run.volumes, Some(vec!["/a:/a".to_string()]));
+
+        let rawhide = &config.containers["rawhide"];
+        assert_eq!(rawhide.tag, "quay.io/org/rawhide:tag");
+        assert_eq!(rawhide.build.build_args, fedora.build.build_args);
+        assert_eq!(rawhide.run.volumes, Some(vec!["/b:/b".to_string()]));
+    }
+
+    #[test]
+    fn test_extends_across_documents() {
+        let config = load(&[
+            "templates:\n  base:\n    build: {context: ctx/}\n    run: {workspace: /}",
+            "containers:\n  app:\n    extends: base\n    tag: app",
+        ])
+        .unwrap();
+
+        assert_eq!(config.containers["app"].build.context, "ctx/");
+    }
+
+    #[test]
+    fn test_extends_errors() {
+        let error = |document| match load(&[document]) {
+            Err(ConfigError::Parse { key, error, .. }) => (key.unwrap(), error),
+            r => panic!("Unexpected result: {:?}", r),
+        };
+
+        assert_eq!(
+            error("containers:\n  app: {extends: missing}"),
+            (
+                "containers.app.extends".to_string(),
+                "no template or container named missing".to_string()
+            )
+        );
+
+        assert_eq!(
+            error("templates:\n  a: {extends: b}\n  b: {extends: a}\ncontainers:\n  app: {extends: a}"),
+            (
+                "templates.b.extends".to_string(),
+                "Extends cycle detected: templates.a -> templates.b -> templates.a".to_string()
+            )
+        );
+
+        let (key, message) = error("templates:\n  base: {build: {context: ctx/}}\ncontainers:\n  app: {extends: base, tag: app}");
+        assert_eq!(key, "containers.app");
+        assert!(message.starts_with("missing field `run`"), "{}", message);
+    }
+
+    #[test]
+    fn test_include() {
+        let dir = std::env::temp_dir().join(format!("denver-include-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("conf.d")).unwrap();
+
+        let container = |name: &str| {
+            format!(
+                "containers:\n  {}:\n    build: {{context: ctx/}}\n    run: {{workspace: /}}\n    tag: {}\n",
+                name, name
+            )
+        };
+        std::fs::write(dir.join("conf.d/a.yml"), container("a")).unwrap();
+        std::fs::write(dir.join("conf.d/b.yml"), container("b")).unwrap();
+        std::fs::write(
+            dir.join("config.yml"),
+            format!("include: [conf.d/*.yml]\n{}", container("main")),
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        loader
+            .load_file(&dir.join("config.yml").to_string_lossy())
+            .unwrap();
+        let config = loader.finish(None).unwrap();
+
+        let mut names: Vec<&String> = config.containers.keys().collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "b", "main"]);
+        assert_eq!(config.sources.len(), 3);
+        assert!(config.sources[2].ends_with("config.yml"));
+
+        // a.yml including config.yml closes a cycle
+        std::fs::write(dir.join("conf.d/a.yml"), "include: [../config.yml]").unwrap();
+
+        let mut loader = Loader::default();
+        match loader.load_file(&dir.join("config.yml").to_string_lossy()) {
+            Err(ConfigError::Parse {
+                path, key, error, ..
+            }) => {
+                assert!(path.unwrap().ends_with("conf.d/a.yml"));
+                assert_eq!(key.as_deref(), Some("include"));
+                assert!(error.starts_with("Include cycle detected"), "{}", error);
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_interpolation() {
+        let dir = std::env::temp_dir().join(format!("denver-interpolate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = std::fs::canonicalize(&dir).unwrap();
+        std::env::set_var("DENVER_TEST_LOADER_TAG", "v1");
+
+        let path = dir.join("config.yml");
+        std::fs::write(
+            &path,
+            r#"
+templates:
+  base:
+    build:
+        context: ./${CONTAINER_NAME}
+    run:
+        workspace: ${CONFIG_DIR}/src
+        volumes: [./data:/data, cache:/cache, /abs:/abs]
+        env_file: [../common.env]
+containers:
+  app:
+    extends: base
+    tag: quay.io/org/${CONTAINER_NAME}:${DENVER_TEST_LOADER_TAG:-latest}
+"#,
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        loader.load_file(&path.to_string_lossy()).unwrap();
+        let config = loader.finish(None).unwrap();
+
+        let app = &config.containers["app"];
+        let root = dir.to_string_lossy();
+        assert_eq!(app.tag, "quay.io/org/app:v1");
+        assert_eq!(app.build.context, format!("{}/app", root));
+        assert_eq!(app.run.workspace, format!("{}/src", root));
+        assert_eq!(
+            app.run.volumes.as_ref().unwrap(),
+            &vec![
+                format!("{}/data:/data", root),
+                "cache:/cache".to_string(),
+                "/abs:/abs".to_string()
+            ]
+        );
+        assert_eq!(
+            app.run.env_file.as_ref().unwrap(),
+            &vec![format!("{}/../common.env", root)]
+        );
+
+        std::fs::write(
+            &path,
+            "templates:\n  x: {build: {context: .}, run: {workspace: .}}\ncontainers:\n  app: {extends: x, tag: '${DENVER_TEST_UNDEFINED}'}\n",
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        loader.load_file(&path.to_string_lossy()).unwrap();
+        match loader.finish(None) {
+            Err(ConfigError::Parse { key, error, .. }) => {
+                assert_eq!(key.as_deref(), Some("containers.app.tag"));
+                assert_eq!(error, "Undefined variable DENVER_TEST_UNDEFINED");
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // Paths set by a template are relative to the template's file
+        std::fs::create_dir_all(dir.join("templates")).unwrap();
+        std::fs::write(
+            dir.join("templates/base.yml"),
+            "templates:\n  base: {build: {context: ./ctx}, run: {workspace: '${CONFIG_DIR}'}}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &path,
+            "include: [templates/base.yml]\ncontainers:\n  app: {extends: base, tag: app, run: {volumes: [./data:/data]}}\n",
+        )
+        .unwrap();
+
+        let mut loader = Loader::default();
+        loader.load_file(&path.to_string_lossy()).unwrap();
+        let config = loader.finish(None).unwrap();
+
+        let app = &config.containers["app"];
+        assert_eq!(app.build.context, format!("{}/templates/ctx", root));
+        assert_eq!(app.run.workspace, format!("{}/templates", root));
+        assert_eq!(
+            app.run.volumes.as_ref().unwrap(),
+            &vec![format!("{}/data:/data", root)]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_interpolate_connection() {
+        std::env::set_var("DENVER_TEST_LOADER_SOCKET", "/run/docker.sock");
+
+        let config = load(&[r#"
+socket: unix://${DENVER_TEST_LOADER_SOCKET}
+tls:
+    cert_path: ${DENVER_TEST_LOADER_CERTS:-/certs}
+containers: {}
+"#])
+        .unwrap();
+        assert_eq!(config.socket.as_deref(), Some("unix:///run/docker.sock"));
+        assert_eq!(config.tls.unwrap().cert_path.as_deref(), Some("/certs"));
+
+        match load(&["profiles:\n  remote: {socket: '${CONTAINER_NAME}'}\n"]) {
+            Err(ConfigError::Parse { key, error, .. }) => {
+                assert_eq!(key.as_deref(), Some("profiles.remote.socket"));
+                assert_eq!(error, "Undefined variable CONTAINER_NAME");
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_profiles() {
+        let document = r#"
+socket: /var/run/docker.sock
+profiles:
+  podman:
+    socket: /run/user/1000/podman/podman.sock
+    engine: podman
+    build_args:
+      engine: podman
+    containers:
+      app:
+        run:
+            args: {user: root}
+        tag: localhost/app:podman
+containers:
+  app:
+    build:
+        context: ctx/
+        build_args:
+          engine: docker
+          user: dev
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+"#;
+
+        let mut loader = Loader::default();
+        loader.load_str(document.to_string(), None).unwrap();
+        let config = loader.finish(None).unwrap();
+        assert_eq!(config.socket.as_deref(), Some("/var/run/docker.sock"));
+        assert_eq!(config.containers["app"].tag, "quay.io/org/app:tag");
+
+        let mut loader = Loader::default();
+        loader.load_str(document.to_string(), None).unwrap();
+        let config = loader.finish(Some("podman")).unwrap();
+
+        let app = &config.containers["app"];
+        assert_eq!(config.engine, Engine::Podman);
+        let build_args = app.build.build_args.as_ref().unwrap();
+        assert_eq!(config.profile.as_deref(), Some("podman"));
+        assert_eq!(
+            config.socket.as_deref(),
+            Some("/run/user/1000/podman/podman.sock")
+        );
+        assert_eq!(build_args["engine"], "podman");
+        assert_eq!(build_args["user"], "dev");
+        assert_eq!(app.run.args.user.as_deref(), Some("root"));
+        assert_eq!(app.tag, "localhost/app:podman");
+
+        let mut loader = Loader::default();
+        loader.load_str(document.to_string(), None).unwrap();
+        match loader.finish(Some("vm")) {
+            Err(e @ ConfigError::UnknownProfile { .. }) => {
+                assert_eq!(
+                    e.to_string(),
+                    "Unknown profile vm, available profiles: podman"
+                )
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+}
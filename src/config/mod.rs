@@ -0,0 +1,906 @@
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+mod args;
+mod environment;
+mod error;
+mod interpolate;
+mod loader;
+mod ports;
+mod validate;
+
+pub use self::args::RunArgs;
+pub use self::environment::resolve as resolve_env;
+pub use self::error::ConfigError;
+use self::loader::Loader;
+pub use self::ports::PortMapping;
+pub use self::validate::validate;
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(rename = "build", deny_unknown_fields)]
+pub struct BuildConfig {
+    pub dockerfile: Option<String>,
+    pub context: String,
+    pub build_args: Option<HashMap<String, String>>,
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(rename = "run", deny_unknown_fields)]
+pub struct RunConfig {
+    #[serde(default, deserialize_with = "args::deserialize")]
+    pub args: RunArgs,
+    pub workspace: String,
+    pub volumes: Option<Vec<String>>,
+    pub entrypoint: Option<String>,
+    pub healthcheck: Option<HealthcheckConfig>,
+    pub network: Option<Vec<NetworkAttachment>>,
+    pub ports: Option<Vec<PortMapping>>,
+    pub env: Option<HashMap<String, String>>,
+    pub env_file: Option<Vec<String>>,
+    // Time given to the container to stop before it's killed
+    #[serde(
+        default,
+        serialize_with = "optional_duration::serialize",
+        deserialize_with = "optional_duration::deserialize_secs"
+    )]
+    pub stop_timeout: Option<Duration>,
+    pub stop_signal: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum NetworkAttachment {
+    Name(String),
+    Aliased { name: String, aliases: Vec<String> },
+}
+
+impl NetworkAttachment {
+    pub fn name(&self) -> &str {
+        match self {
+            NetworkAttachment::Name(name) | NetworkAttachment::Aliased { name, .. } => name,
+        }
+    }
+
+    pub fn aliases(&self) -> Vec<&str> {
+        match self {
+            NetworkAttachment::Name(_) => vec![],
+            NetworkAttachment::Aliased { aliases, .. } => {
+                aliases.iter().map(|a| a.as_str()).collect()
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(untagged)]
+pub enum HealthcheckCommand {
+    Shell(String),
+    Exec(Vec<String>),
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(rename = "healthcheck", deny_unknown_fields)]
+pub struct HealthcheckConfig {
+    pub command: HealthcheckCommand,
+    #[serde(default, with = "optional_duration")]
+    pub interval: Option<Duration>,
+    #[serde(default, with = "optional_duration")]
+    pub timeout: Option<Duration>,
+    pub retries: Option<u32>,
+    #[serde(default, with = "optional_duration")]
+    pub start_period: Option<Duration>,
+}
+
+impl HealthcheckConfig {
+    // Docker defaults, used when a value is not set
+    const INTERVAL: Duration = Duration::from_secs(30);
+    const TIMEOUT: Duration = Duration::from_secs(30);
+    const RETRIES: u32 = 3;
+
+    pub fn test(&self) -> Vec<&str> {
+        match &self.command {
+            HealthcheckCommand::Shell(command) => vec!["CMD-SHELL", command],
+            HealthcheckCommand::Exec(command) => {
+                let mut test = vec!["CMD"];
+                test.extend(command.iter().map(|s| s.as_str()));
+                test
+            }
+        }
+    }
+
+    // Longest time it can take docker to flag the container as healthy
+    pub fn max_wait(&self) -> Duration {
+        let interval = self.interval.unwrap_or(Self::INTERVAL);
+        let timeout = self.timeout.unwrap_or(Self::TIMEOUT);
+        let retries = self.retries.unwrap_or(Self::RETRIES);
+
+        self.start_period.unwrap_or_default() + (interval + timeout) * retries
+    }
+}
+
+mod optional_duration {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    use crate::duration;
+
+    pub fn serialize<S>(d: &Option<Duration>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match d {
+            Some(d) => serializer.serialize_str(&duration::format(d)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_with(deserializer, duration::parse)
+    }
+
+    pub fn deserialize_secs<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        parse_with(deserializer, duration::parse_secs)
+    }
+
+    fn parse_with<'de, D>(
+        deserializer: D,
+        parse: fn(&str) -> Result<Duration, String>,
+    ) -> Result<Option<Duration>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(d) => parse(&d).map(Some).map_err(serde::de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ContainerConfig {
+    pub build: BuildConfig,
+    pub run: RunConfig,
+    pub tag: String,
+    pub depends_on: Option<Vec<String>>,
+    // File the container was defined in
+    #[serde(skip)]
+    pub source: Option<String>,
+}
+
+// JSON with the keys of every map sorted, so equal values always serialize
+// the same. serde_json only sorts them itself without preserve_order.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Sorted {
+    Map(BTreeMap<String, Sorted>),
+    List(Vec<Sorted>),
+    Scalar(serde_json::Value),
+}
+
+impl Sorted {
+    fn to_json<T: Serialize>(value: &T) -> Result<String, String> {
+        let value = serde_json::to_value(value)
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))?;
+
+        serde_json::to_string(&Sorted::from(value))
+            .map_err(|e| format!("Failed to serialize configuration: {}", e))
+    }
+}
+
+impl From<serde_json::Value> for Sorted {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Object(map) => {
+                Sorted::Map(map.into_iter().map(|(k, v)| (k, v.into())).collect())
+            }
+            serde_json::Value::Array(list) => {
+                Sorted::List(list.into_iter().map(Sorted::from).collect())
+            }
+            value => Sorted::Scalar(value),
+        }
+    }
+}
+
+impl ContainerConfig {
+    // Identifies a container created from this configuration and image, as
+    // ContainerEngine::image_id reports it. Env files and networks are hashed
+    // by what they resolve to, so editing them is noticed as well.
+    pub fn hash(
+        &self,
+        image_id: &str,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<String, String> {
+        let env = resolve_env(&self.run)?;
+        let attached: BTreeMap<&str, Option<&NetworkConfig>> = self
+            .run
+            .network
+            .iter()
+            .flatten()
+            .map(|n| {
+                (
+                    n.name(),
+                    networks.and_then(|networks| networks.get(n.name())),
+                )
+            })
+            .collect();
+
+        let mut hasher = Sha256::new();
+        hasher.update(Sorted::to_json(self)?);
+        hasher.update(env.join("\n"));
+        hasher.update(Sorted::to_json(&attached)?);
+        hasher.update(image_id);
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    // Volumes referenced by name rather than by a host path, docker creates
+    // them the first time they are used.
+    pub fn named_volumes(&self) -> Vec<&str> {
+        self.run
+            .volumes
+            .iter()
+            .flatten()
+            .filter_map(|volume| volume.split_once(':'))
+            .map(|(host, _)| host)
+            .filter(|host| !host.starts_with('.') && !host.contains('/'))
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug)]
+#[serde(rename = "network", deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub driver: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug, Clone)]
+#[serde(rename = "tls", deny_unknown_fields)]
+pub struct TlsConfig {
+    pub cert_path: Option<String>,
+    // Checks the daemon against ca.pem in cert_path, the system's CAs are
+    // used otherwise
+    pub verify: Option<bool>,
+}
+
+// Container engine behind the socket, their APIs differ in a few places
+#[derive(Deserialize, Serialize, Eq, PartialEq, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    #[default]
+    Docker,
+    Podman,
+}
+
+#[derive(Serialize, Eq, PartialEq, Debug, Default)]
+pub struct Config {
+    pub socket: Option<String>,
+    pub tls: Option<TlsConfig>,
+    pub engine: Engine,
+    // Profile applied over the configuration, if any
+    #[serde(skip)]
+    pub profile: Option<String>,
+    pub containers: HashMap<String, ContainerConfig>,
+    pub networks: Option<HashMap<String, NetworkConfig>>,
+    // Files the configuration was loaded from
+    #[serde(skip)]
+    pub sources: Vec<String>,
+}
+
+// Name of the per-project configuration file
+const PROJECT_CONFIG: &str = ".denver.yml";
+
+// Global configuration used when none is given
+pub const DEFAULT_CONFIG: &str = "~/.config/denver/config.yml";
+
+impl Config {
+    pub fn new(config: &str) -> Result<Self, ConfigError> {
+        let mut loader = Loader::default();
+        loader.load_str(config.to_string(), None)?;
+        loader.finish(None)
+    }
+
+    // Fills in defaults, so the rest of denver can use the configuration as
+    // is.
+    fn resolve(&mut self) {
+        for cert_path in self.tls.iter_mut().flat_map(|tls| tls.cert_path.as_mut()) {
+            *cert_path = expand_home(cert_path);
+        }
+
+        for container in self.containers.values_mut() {
+            container
+                .build
+                .dockerfile
+                .get_or_insert_with(|| "Dockerfile".to_string());
+        }
+    }
+}
+
+fn expand_home(path: &str) -> String {
+    match path.strip_prefix('~') {
+        Some(relative_path) if relative_path.is_empty() || relative_path.starts_with('/') => {
+            format!("{}{}", env::var("HOME").unwrap_or_default(), relative_path)
+        }
+        _ => path.to_owned(),
+    }
+}
+
+// Walks up from dir looking for a project configuration
+fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG))
+        .find(|path| path.is_file())
+}
+
+// Paths are compared once resolved, a file can be named in many ways
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (std::fs::canonicalize(a), std::fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+// Loads the global configuration and merges the closest project
+// configuration over it. The default global file is optional if a project
+// one exists, a file given explicitly isn't.
+pub fn read_config(config: Option<&str>, profile: Option<&str>) -> Result<Config, ConfigError> {
+    let project = env::current_dir()
+        .ok()
+        .and_then(|dir| find_project_config(&dir));
+
+    load_config(config, project, profile)
+}
+
+fn load_config(
+    config: Option<&str>,
+    project: Option<PathBuf>,
+    profile: Option<&str>,
+) -> Result<Config, ConfigError> {
+    let global = expand_home(config.unwrap_or(DEFAULT_CONFIG));
+    let project = project.filter(|path| !same_file(path, Path::new(&global)));
+
+    let mut loader = Loader::default();
+    if config.is_some() || project.is_none() || Path::new(&global).exists() {
+        loader.load_file(&global)?;
+    }
+
+    if let Some(project) = project {
+        loader.load_file(&project.to_string_lossy())?;
+    }
+
+    let mut config = loader.finish(profile)?;
+    config.resolve();
+
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_deserialize() {
+        let socket = "/sockets/docker/fedora.sock/";
+        let name = "test-container";
+        let dockerfile = "Dockerfile";
+        let context = "ctx/";
+        let build_args = r#"
+            test_arg: value
+            test_arg2: value2
+            "#;
+        let args = r#"
+        - i
+        - rm"#;
+        let workspace = "/some/path/";
+        let volumes = r#"
+        - /some/other/path:/path
+        - /dev:/dev:ro"#;
+        let entrypoint = "entrypoint";
+        let tag = "quay.io/org/some:tag";
+        let config = format!(
+            r#"
+socket: {}
+containers:
+  {}:
+    build:
+        dockerfile: {}
+        context: {}
+        build_args: {}
+    run:
+        args: {}
+        workspace: {}
+        volumes: {}
+        entrypoint: {}
+    tag: {}
+        "#,
+            socket,
+            name,
+            dockerfile,
+            context,
+            build_args,
+            args,
+            workspace,
+            volumes,
+            entrypoint,
+            tag
+        );
+
+        let config = Config::new(&config).unwrap();
+
+        assert_eq!(config.socket.as_deref(), Some(socket));
+        assert_eq!(config.containers.len(), 1);
+
+        let container = &config.containers[name];
+        assert_eq!(tag, container.tag);
+
+        let build_config = &container.build;
+        assert_eq!(dockerfile, build_config.dockerfile.as_ref().unwrap());
+        assert_eq!(context, build_config.context);
+        for (k, v) in build_config.build_args.as_ref().unwrap() {
+            assert!(build_args.contains(k));
+            assert!(build_args.contains(v));
+        }
+
+        let run_config = &container.run;
+        assert!(run_config.args.interactive);
+        assert!(run_config.args.auto_remove);
+        assert!(!run_config.args.privileged);
+        assert_eq!(workspace, run_config.workspace);
+
+        let run_volumes = run_config.volumes.as_ref().unwrap();
+        assert_eq!(2, run_volumes.len());
+        for volume in run_volumes {
+            assert!(volumes.contains(volume));
+        }
+
+        assert_eq!(entrypoint, run_config.entrypoint.as_ref().unwrap());
+    }
+
+    #[test]
+    fn test_healthcheck() {
+        let config = r#"
+socket: /sock
+containers:
+  db:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        healthcheck:
+            command: pg_isready -U postgres
+            interval: 5s
+            retries: 10
+            start_period: 1m
+    tag: quay.io/org/db:tag
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        healthcheck:
+            command: [curl, -f, http://localhost]
+    tag: quay.io/org/app:tag
+        "#;
+
+        let config = Config::new(config).unwrap();
+
+        let db = config.containers["db"].run.healthcheck.as_ref().unwrap();
+        assert_eq!(db.test(), vec!["CMD-SHELL", "pg_isready -U postgres"]);
+        assert_eq!(db.interval, Some(Duration::from_secs(5)));
+        assert_eq!(db.timeout, None);
+        assert_eq!(db.max_wait(), Duration::from_secs(60 + 35 * 10));
+
+        let app = config.containers["app"].run.healthcheck.as_ref().unwrap();
+        assert_eq!(app.test(), vec!["CMD", "curl", "-f", "http://localhost"]);
+        assert_eq!(app.max_wait(), Duration::from_secs(180));
+    }
+
+    #[test]
+    fn test_stop() {
+        let text = r#"
+containers:
+  db:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        stop_timeout: 1m
+        stop_signal: SIGINT
+    tag: quay.io/org/db:tag
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+        "#;
+
+        let config = Config::new(text).unwrap();
+
+        let db = &config.containers["db"].run;
+        assert_eq!(db.stop_timeout, Some(Duration::from_secs(60)));
+        assert_eq!(db.stop_signal.as_deref(), Some("SIGINT"));
+
+        let app = &config.containers["app"].run;
+        assert_eq!(app.stop_timeout, None);
+        assert_eq!(app.stop_signal, None);
+
+        // Docker would kill the container right away
+        assert!(Config::new(&text.replace("1m", "500ms")).is_err());
+    }
+
+    #[test]
+    fn test_networks() {
+        let config = r#"
+socket: /sock
+networks:
+  backend:
+    driver: bridge
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        network:
+          - frontend
+          - name: backend
+            aliases: [api, app.local]
+    tag: quay.io/org/app:tag
+        "#;
+
+        let config = Config::new(config).unwrap();
+
+        let networks = config.networks.as_ref().unwrap();
+        assert_eq!(networks["backend"].driver.as_deref(), Some("bridge"));
+
+        let attachments = config.containers["app"].run.network.as_ref().unwrap();
+        assert_eq!(attachments[0].name(), "frontend");
+        assert!(attachments[0].aliases().is_empty());
+        assert_eq!(attachments[1].name(), "backend");
+        assert_eq!(attachments[1].aliases(), vec!["api", "app.local"]);
+    }
+
+    #[test]
+    fn test_ports() {
+        let config = r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        ports:
+        - 8080:80
+        - 127.0.0.1:5353:53/udp
+    tag: quay.io/org/app:tag
+        "#;
+
+        let config = Config::new(config).unwrap();
+
+        let ports = config.containers["app"].run.ports.as_ref().unwrap();
+        assert_eq!(2, ports.len());
+        assert_eq!("8080:80/tcp", ports[0].to_string());
+        assert_eq!("127.0.0.1:5353:53/udp", ports[1].to_string());
+    }
+
+    #[test]
+    fn test_invalid_ports() {
+        let config = r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        ports: [8080:80/http]
+    tag: quay.io/org/app:tag
+        "#;
+
+        match Config::new(config) {
+            Err(ConfigError::Parse { key, error, .. }) => {
+                assert_eq!(key.as_deref(), Some("containers.app.run.ports[0]"));
+                assert_eq!(error, "Invalid protocol 'http' in 8080:80/http");
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_missing_file() {
+        match read_config(Some("/does/not/exist/config.yml"), None) {
+            Err(ConfigError::Read { path, .. }) => assert_eq!(path, "/does/not/exist/config.yml"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_load_config() {
+        let dir = std::env::temp_dir().join(format!("denver-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let project = dir.join(PROJECT_CONFIG);
+        std::fs::write(&project, "containers: {}\n").unwrap();
+
+        // Only the default configuration may be missing
+        match load_config(
+            Some("/does/not/exist/config.yml"),
+            Some(project.clone()),
+            None,
+        ) {
+            Err(ConfigError::Read { path, .. }) => assert_eq!(path, "/does/not/exist/config.yml"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // The project configuration given as the global one is loaded once
+        let same = dir.join(".").join(PROJECT_CONFIG);
+        let config = load_config(same.to_str(), Some(project), None).unwrap();
+        assert_eq!(config.sources, vec![same.to_string_lossy().to_string()]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn parse_error(config: &str) -> ConfigError {
+        // Tests run in parallel, each call needs a file of its own
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+        let path = std::env::temp_dir().join(format!(
+            "denver-config-{}-{}.yml",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&path, config).unwrap();
+
+        let result = read_config(path.to_str(), None);
+        std::fs::remove_file(&path).unwrap();
+
+        match result {
+            Err(e @ ConfigError::Parse { .. }) => {
+                let message = e.to_string();
+                assert!(message.starts_with(path.to_str().unwrap()), "{}", message);
+                e
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_bad_yaml() {
+        let error = parse_error("socket: /sock\n  containers: {}\n");
+
+        match error {
+            ConfigError::Parse { line, key, .. } => {
+                assert_eq!(line, Some(2));
+                assert_eq!(key, None);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let error = parse_error(
+            r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        workdir: /some/path/
+    tag: quay.io/org/app:tag
+"#,
+        );
+
+        match error {
+            ConfigError::Parse {
+                line,
+                column,
+                key,
+                error,
+                ..
+            } => {
+                assert_eq!((line, column), (Some(9), Some(9)));
+                assert_eq!(key.as_deref(), Some("containers.app.run.workdir"));
+                assert!(error.starts_with("unknown field `workdir`"), "{}", error);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_wrong_type() {
+        let error = parse_error(
+            r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: ctx/
+        build_args: [a, b]
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+"#,
+        );
+
+        match error {
+            ConfigError::Parse { key, error, .. } => {
+                assert_eq!(key.as_deref(), Some("containers.app.build.build_args"));
+                assert!(error.starts_with("invalid type: sequence"), "{}", error);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_named_volumes() {
+        let config = Config::new(
+            r#"
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        volumes:
+        - cache:/root/.cache
+        - /dev:/dev:ro
+        - ./src:/src
+        - .:/workspace
+        - data:/data:z
+        - /anonymous
+    tag: quay.io/org/app:tag
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.containers["app"].named_volumes(),
+            vec!["cache", "data"]
+        );
+    }
+
+    #[test]
+    fn test_hash() {
+        let config = |env: &str| {
+            let config = format!(
+                "containers:\n  app:\n    build: {{context: ctx/}}\n    run: {{workspace: /, env: {}}}\n    tag: app",
+                env
+            );
+            Config::new(&config)
+                .unwrap()
+                .containers
+                .remove("app")
+                .unwrap()
+        };
+
+        let hash = config("{A: a, B: b, C: c}").hash("1", None).unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, config("{C: c, B: b, A: a}").hash("1", None).unwrap());
+        assert_ne!(hash, config("{A: a, B: b, C: d}").hash("1", None).unwrap());
+        assert_ne!(hash, config("{A: a, B: b, C: c}").hash("2", None).unwrap());
+    }
+
+    #[test]
+    fn test_sorted() {
+        let value = serde_json::json!({"b": [{"d": 1, "c": 2}], "a": null});
+        assert_eq!(
+            Sorted::to_json(&value).unwrap(),
+            r#"{"a":null,"b":[{"c":2,"d":1}]}"#
+        );
+    }
+
+    #[test]
+    fn test_hash_resolved() {
+        let dir = std::env::temp_dir().join(format!("denver-hash-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let env_file = dir.join("app.env");
+        std::fs::write(&env_file, "A=a\n").unwrap();
+
+        let config = Config::new(&format!(
+            r#"
+networks:
+  backend:
+    driver: bridge
+containers:
+  app:
+    build: {{context: ctx/}}
+    run:
+      workspace: /
+      env_file: [{}]
+      network: [backend]
+    tag: app
+"#,
+            env_file.display()
+        ))
+        .unwrap();
+        let app = &config.containers["app"];
+        let hash = app.hash("1", config.networks.as_ref()).unwrap();
+
+        // The file's contents changed, not its path
+        std::fs::write(&env_file, "A=b\n").unwrap();
+        let edited = app.hash("1", config.networks.as_ref()).unwrap();
+        assert_ne!(hash, edited);
+
+        let networks = HashMap::from([(
+            "backend".to_string(),
+            NetworkConfig {
+                driver: Some("overlay".to_string()),
+            },
+        )]);
+        assert_ne!(edited, app.hash("1", Some(&networks)).unwrap());
+
+        std::fs::remove_file(&env_file).unwrap();
+        assert!(app.hash("1", None).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_config() {
+        let root = std::env::temp_dir().join(format!("denver-project-{}", std::process::id()));
+        let nested = root.join("src/deeply/nested");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_project_config(&nested), None);
+
+        std::fs::write(root.join(PROJECT_CONFIG), "containers: {}").unwrap();
+        assert_eq!(
+            find_project_config(&nested),
+            Some(root.join(PROJECT_CONFIG))
+        );
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_merge() {
+        let container = |tag: &str| {
+            format!(
+                r#"
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: {}"#,
+                tag
+            )
+        };
+        let global = format!(
+            "socket: /global.sock\ncontainers:\n  app:{}\n  db:{}",
+            container("global/app"),
+            container("global/db")
+        );
+        let project = format!(
+            "containers:\n  app:{}\n  tools:{}",
+            container("project/app"),
+            container("project/tools")
+        );
+
+        let mut loader = Loader::default();
+        loader.load_str(global, None).unwrap();
+        loader.load_str(project, None).unwrap();
+        let config = loader.finish(None).unwrap();
+
+        assert_eq!(config.socket.as_deref(), Some("/global.sock"));
+        assert_eq!(config.containers.len(), 3);
+        assert_eq!(config.containers["app"].tag, "project/app");
+        assert_eq!(config.containers["db"].tag, "global/db");
+        assert_eq!(config.containers["tools"].tag, "project/tools");
+    }
+}
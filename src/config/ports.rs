@@ -0,0 +1,182 @@
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize, Serializer};
+
+// A port publishing rule, written as `[ip:]host:container[/protocol]`, where
+// both host and container ports can be ranges of the same length, i.e.
+// `127.0.0.1:8000-8010:8000-8010/udp`. IPv6 addresses need to be wrapped in
+// brackets, as in `[::1]:8080:80`.
+#[derive(Deserialize, Eq, PartialEq, Debug)]
+#[serde(try_from = "String")]
+pub struct PortMapping {
+    pub host_ip: Option<String>,
+    pub host: RangeInclusive<u16>,
+    pub container: RangeInclusive<u16>,
+    pub protocol: String,
+}
+
+impl PortMapping {
+    // Pairs of (host, container) ports to be published
+    pub fn pairs(&self) -> impl Iterator<Item = (u16, u16)> {
+        self.host.clone().zip(self.container.clone())
+    }
+}
+
+fn parse_range(range: &str, mapping: &str) -> Result<RangeInclusive<u16>, String> {
+    let parse_port = |port: &str| match port.parse::<u16>() {
+        Ok(0) | Err(_) => Err(format!("Invalid port '{}' in {}", port, mapping)),
+        Ok(port) => Ok(port),
+    };
+
+    let range = match range.split_once('-') {
+        Some((start, end)) => parse_port(start)?..=parse_port(end)?,
+        None => {
+            let port = parse_port(range)?;
+            port..=port
+        }
+    };
+
+    if range.is_empty() {
+        return Err(format!("Invalid port range in {}", mapping));
+    }
+
+    Ok(range)
+}
+
+impl TryFrom<String> for PortMapping {
+    type Error = String;
+
+    fn try_from(mapping: String) -> Result<Self, Self::Error> {
+        let (ports, protocol) = match mapping.split_once('/') {
+            Some((ports, protocol)) => (ports, protocol),
+            None => (mapping.as_str(), "tcp"),
+        };
+
+        if !["tcp", "udp", "sctp"].contains(&protocol) {
+            return Err(format!("Invalid protocol '{}' in {}", protocol, mapping));
+        }
+
+        let (host_ip, ports) = if let Some(ports) = ports.strip_prefix('[') {
+            match ports.split_once("]:") {
+                Some((ip, ports)) => (Some(ip), ports),
+                None => return Err(format!("Invalid IPv6 address in {}", mapping)),
+            }
+        } else if ports.matches(':').count() == 2 {
+            let (ip, ports) = ports.split_once(':').unwrap();
+            (Some(ip), ports)
+        } else {
+            (None, ports)
+        };
+
+        let (host, container) = match ports.split_once(':') {
+            Some((host, container)) => (
+                parse_range(host, &mapping)?,
+                parse_range(container, &mapping)?,
+            ),
+            None => {
+                return Err(format!(
+                    "Expected host:container[/protocol], got {}",
+                    mapping
+                ))
+            }
+        };
+
+        if host.len() != container.len() {
+            return Err(format!(
+                "Host and container port ranges differ in size in {}",
+                mapping
+            ));
+        }
+
+        if let Some(ip) = host_ip {
+            if ip.parse::<std::net::IpAddr>().is_err() {
+                return Err(format!("Invalid host IP '{}' in {}", ip, mapping));
+            }
+        }
+
+        Ok(PortMapping {
+            host_ip: host_ip.map(|ip| ip.to_string()),
+            host,
+            container,
+            protocol: protocol.to_string(),
+        })
+    }
+}
+
+impl Serialize for PortMapping {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl Display for PortMapping {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let range = |r: &RangeInclusive<u16>| {
+            if r.start() == r.end() {
+                r.start().to_string()
+            } else {
+                format!("{}-{}", r.start(), r.end())
+            }
+        };
+
+        match &self.host_ip {
+            Some(ip) if ip.contains(':') => write!(f, "[{}]:", ip)?,
+            Some(ip) => write!(f, "{}:", ip)?,
+            None => {}
+        }
+
+        write!(
+            f,
+            "{}:{}/{}",
+            range(&self.host),
+            range(&self.container),
+            self.protocol
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(mapping: &str) -> Result<PortMapping, String> {
+        PortMapping::try_from(mapping.to_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        let mapping = parse("8080:80").unwrap();
+        assert_eq!(mapping.host_ip, None);
+        assert_eq!(mapping.pairs().collect::<Vec<_>>(), vec![(8080, 80)]);
+        assert_eq!(mapping.protocol, "tcp");
+
+        let mapping = parse("127.0.0.1:5353:53/udp").unwrap();
+        assert_eq!(mapping.host_ip.as_deref(), Some("127.0.0.1"));
+        assert_eq!(mapping.pairs().collect::<Vec<_>>(), vec![(5353, 53)]);
+        assert_eq!(mapping.protocol, "udp");
+
+        let mapping = parse("[::1]:9000-9002:8000-8002").unwrap();
+        assert_eq!(mapping.host_ip.as_deref(), Some("::1"));
+        assert_eq!(
+            mapping.pairs().collect::<Vec<_>>(),
+            vec![(9000, 8000), (9001, 8001), (9002, 8002)]
+        );
+        assert_eq!(mapping.to_string(), "[::1]:9000-9002:8000-8002/tcp");
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("80").is_err());
+        assert!(parse("8080:80/http").is_err());
+        assert!(parse("8080:0").is_err());
+        assert!(parse("8080:70000").is_err());
+        assert!(parse("9000-9001:8000-8002").is_err());
+        assert!(parse("9001-9000:8001-8000").is_err());
+        assert!(parse("localhost:8080:80").is_err());
+        assert!(parse("[::1:8080:80").is_err());
+    }
+}
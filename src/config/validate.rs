@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use regex::Regex;
+
+use super::{Config, ContainerConfig};
+
+// Loosely follows the grammar used by docker for image references:
+// [domain[:port]/]path[:tag][@digest]
+const REFERENCE: &str = concat!(
+    r"^(?:[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?",
+    r"(?:\.[a-zA-Z0-9](?:[a-zA-Z0-9-]*[a-zA-Z0-9])?)*(?::[0-9]+)?/)?",
+    r"[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*",
+    r"(?:/[a-z0-9]+(?:(?:[._]|__|-+)[a-z0-9]+)*)*",
+    r"(?::[\w][\w.-]{0,127})?",
+    r"(?:@sha256:[a-f0-9]{64})?$",
+);
+
+fn validate_container(name: &str, container: &ContainerConfig, problems: &mut Vec<String>) {
+    let mut check = |path: &str, what: &str| {
+        if !Path::new(path).exists() {
+            problems.push(format!("{}: {} {} does not exist", name, what, path));
+        }
+    };
+
+    let build = &container.build;
+    check(&build.context, "build context");
+
+    if let Some(dockerfile) = &build.dockerfile {
+        let dockerfile = Path::new(&build.context).join(dockerfile);
+        check(&dockerfile.to_string_lossy(), "Dockerfile");
+    }
+
+    let run = &container.run;
+    check(&run.workspace, "workspace");
+
+    for volume in run.volumes.iter().flatten() {
+        let host = volume.split(':').next().unwrap_or_default();
+
+        // Anything else is a named volume, managed by docker
+        if host.starts_with('/') || host.starts_with('.') {
+            check(host, "volume host path");
+        }
+    }
+
+    for env_file in run.env_file.iter().flatten() {
+        check(env_file, "env file");
+    }
+
+    let reference = Regex::new(REFERENCE).unwrap();
+    if !reference.is_match(&container.tag) {
+        problems.push(format!(
+            "{}: tag {} is not a valid image reference",
+            name, container.tag
+        ));
+    }
+}
+
+// Checks the configuration against the host, returning every problem found
+pub fn validate(config: &Config) -> Vec<String> {
+    let mut problems = vec![];
+    let mut names: Vec<&String> = config.containers.keys().collect();
+    names.sort();
+
+    for name in names {
+        validate_container(name, &config.containers[name], &mut problems);
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference() {
+        let reference = Regex::new(REFERENCE).unwrap();
+
+        for valid in [
+            "fedora",
+            "fedora:36",
+            "quay.io/org/some:tag",
+            "localhost:5000/my-image",
+            "registry.example.com/a/b/c_d__e.f:v1.2-rc",
+            "alpine@sha256:1e014f84205d569a5cc3be4e108ca614055f7e21d11928946113ab3f36054801",
+        ] {
+            assert!(reference.is_match(valid), "{}", valid);
+        }
+
+        for invalid in [
+            "",
+            "Fedora",
+            "fedora:",
+            "-fedora",
+            "org//image",
+            "image:t@g",
+        ] {
+            assert!(!reference.is_match(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = r#"
+socket: /sock
+containers:
+  app:
+    build:
+        context: /does/not/exist
+        dockerfile: Containerfile
+    run:
+        workspace: /
+        volumes:
+          - /also/missing:/data
+          - cache:/cache
+          - /:/host
+    tag: Not-A-Tag
+        "#;
+        let config = Config::new(config).unwrap();
+
+        assert_eq!(
+            validate(&config),
+            vec![
+                "app: build context /does/not/exist does not exist",
+                "app: Dockerfile /does/not/exist/Containerfile does not exist",
+                "app: volume host path /also/missing does not exist",
+                "app: tag Not-A-Tag is not a valid image reference",
+            ]
+        );
+    }
+}
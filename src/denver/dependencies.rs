@@ -0,0 +1,131 @@
+use crate::config::Config;
+
+use super::DenverError;
+
+// Resolves the dependency closure of the given containers. Dependencies are
+// always placed before the containers that need them, so the result can be
+// used as is for starting containers and in reverse for stopping them.
+pub fn resolve<'a>(config: &'a Config, names: &[&str]) -> Result<Vec<&'a String>, DenverError> {
+    let mut order = vec![];
+    let mut visiting = vec![];
+
+    for name in names {
+        visit(config, name, &mut visiting, &mut order)?;
+    }
+
+    Ok(order)
+}
+
+fn visit<'a>(
+    config: &'a Config,
+    name: &str,
+    visiting: &mut Vec<&'a String>,
+    order: &mut Vec<&'a String>,
+) -> Result<(), DenverError> {
+    if order.iter().any(|n| *n == name) {
+        return Ok(());
+    }
+
+    if let Some(start) = visiting.iter().position(|n| *n == name) {
+        let cycle = visiting[start..]
+            .iter()
+            .map(|n| n.as_str())
+            .chain([name])
+            .collect::<Vec<&str>>()
+            .join(" -> ");
+
+        return Err(DenverError::DependencyCycle(format!(
+            "Dependency cycle detected: {}",
+            cycle
+        )));
+    }
+
+    let (name, container) = match config.containers.get_key_value(name) {
+        Some(entry) => entry,
+        None => {
+            let error = match visiting.last() {
+                Some(parent) => format!("{} not found, required by {}", name, parent),
+                None => format!("{} not found", name),
+            };
+            return Err(DenverError::UnknownContainer(error));
+        }
+    };
+
+    visiting.push(name);
+    for dependency in container.depends_on.iter().flatten() {
+        visit(config, dependency, visiting, order)?;
+    }
+    visiting.pop();
+
+    order.push(name);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn container(name: &str, depends_on: &[&str]) -> String {
+        format!(
+            r#"
+  {}:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/{}:tag
+    depends_on: [{}]"#,
+            name,
+            name,
+            depends_on.join(", ")
+        )
+    }
+
+    fn config(containers: &[(&str, &[&str])]) -> Config {
+        let containers: String = containers
+            .iter()
+            .map(|(name, depends_on)| container(name, depends_on))
+            .collect();
+
+        Config::new(&format!("socket: /sock\ncontainers:{}", containers)).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_order() {
+        let config = config(&[
+            ("app", &["db", "cache"]),
+            ("db", &[]),
+            ("cache", &["db"]),
+            ("unrelated", &[]),
+        ]);
+
+        let order = resolve(&config, &["app"]).unwrap();
+        assert_eq!(order, vec!["db", "cache", "app"]);
+
+        let order = resolve(&config, &["cache", "unrelated"]).unwrap();
+        assert_eq!(order, vec!["db", "cache", "unrelated"]);
+    }
+
+    #[test]
+    fn test_resolve_cycle() {
+        let config = config(&[("app", &["db"]), ("db", &["cache"]), ("cache", &["app"])]);
+
+        match resolve(&config, &["app"]) {
+            Err(DenverError::DependencyCycle(e)) => {
+                assert_eq!(e, "Dependency cycle detected: app -> db -> cache -> app")
+            }
+            _ => panic!("Cycle not detected"),
+        }
+    }
+
+    #[test]
+    fn test_resolve_unknown() {
+        let config = config(&[("app", &["db"])]);
+
+        match resolve(&config, &["app"]) {
+            Err(DenverError::UnknownContainer(e)) => assert_eq!(e, "db not found, required by app"),
+            _ => panic!("Unknown dependency not detected"),
+        }
+    }
+}
@@ -0,0 +1,321 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use serde_json::json;
+use shiplift::rep::Container;
+
+use crate::cli::Common;
+use crate::config::{ContainerConfig, NetworkConfig};
+use crate::docker::{ContainerEngine, Details, DockerError, Health, Usage, HASH_LABEL};
+
+struct FakeContainer {
+    id: String,
+    name: String,
+    image: String,
+    image_id: String,
+    state: String,
+    hash: Option<String>,
+    health: Health,
+}
+
+// An in-memory container engine, keeping track of the calls made to it
+#[derive(Default)]
+pub struct FakeEngine {
+    containers: RefCell<Vec<FakeContainer>>,
+    // Image IDs by tag
+    images: RefCell<HashMap<String, String>>,
+    networks: RefCell<Vec<String>>,
+    volumes: RefCell<Vec<String>>,
+    calls: RefCell<Vec<String>>,
+    // Timeouts given to stop_container, in order
+    timeouts: RefCell<Vec<Duration>>,
+    created: Cell<usize>,
+}
+
+impl FakeEngine {
+    pub fn with_running(containers: &[(&str, &str)]) -> Self {
+        let engine = FakeEngine::default();
+
+        for (name, image) in containers {
+            let id = engine.next_id();
+            let image_id = engine.tag(image);
+            engine.containers.borrow_mut().push(FakeContainer {
+                id,
+                name: name.to_string(),
+                image: image.to_string(),
+                image_id,
+                state: "running".to_string(),
+                hash: None,
+                health: Health::Healthy,
+            });
+        }
+
+        engine
+    }
+
+    pub fn add_network(&self, name: &str) {
+        self.networks.borrow_mut().push(name.to_string());
+    }
+
+    pub fn add_volume(&self, name: &str) {
+        self.volumes.borrow_mut().push(name.to_string());
+    }
+
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn timeouts(&self) -> Vec<Duration> {
+        self.timeouts.borrow().clone()
+    }
+
+    pub fn id_of(&self, name: &str) -> Option<String> {
+        self.containers
+            .borrow()
+            .iter()
+            .find(|c| c.name == name)
+            .map(|c| c.id.clone())
+    }
+
+    // IDs are unique on their first 12 characters, like docker's
+    fn next_id(&self) -> String {
+        self.created.set(self.created.get() + 1);
+        format!("{:012x}{}", self.created.get(), "0".repeat(52))
+    }
+
+    // Points the tag to a new image, IDs are kept without the sha256: prefix
+    // as DockerClient does
+    pub fn tag(&self, tag: &str) -> String {
+        let id = self.next_id();
+        self.images.borrow_mut().insert(tag.to_string(), id.clone());
+        id
+    }
+
+    pub fn set_health(&self, name: &str, health: Health) {
+        if let Some(container) = self
+            .containers
+            .borrow_mut()
+            .iter_mut()
+            .find(|c| c.name == name)
+        {
+            container.health = health;
+        }
+    }
+
+    fn call(&self, call: String) {
+        self.calls.borrow_mut().push(call);
+    }
+
+    fn set_state(&self, id: &str, state: &str) -> Result<(), DockerError> {
+        match self.containers.borrow_mut().iter_mut().find(|c| c.id == id) {
+            Some(container) => {
+                container.state = state.to_string();
+                Ok(())
+            }
+            None => Err(DockerError::Run(format!("No such container: {}", id))),
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl ContainerEngine for FakeEngine {
+    async fn build_image(
+        &self,
+        _args: &Common,
+        container: &ContainerConfig,
+    ) -> Result<(), DockerError> {
+        self.call(format!("build {}", container.tag));
+        self.tag(&container.tag);
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        name: &str,
+        container: &ContainerConfig,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<String, DockerError> {
+        if self.id_of(name).is_some() {
+            return Err(DockerError::Run(format!(
+                "Conflict: {} already exists",
+                name
+            )));
+        }
+
+        let image_id = match self.images.borrow().get(&container.tag) {
+            Some(id) => id.clone(),
+            None => {
+                return Err(DockerError::Run(format!(
+                    "No such image: {}",
+                    container.tag
+                )))
+            }
+        };
+
+        let id = self.next_id();
+        let hash = container
+            .hash(&image_id, networks)
+            .map_err(DockerError::Run)?;
+        self.call(format!("create {}", name));
+        self.containers.borrow_mut().push(FakeContainer {
+            id: id.clone(),
+            name: name.to_string(),
+            image: container.tag.clone(),
+            image_id,
+            state: "created".to_string(),
+            hash: Some(hash),
+            health: Health::Healthy,
+        });
+
+        Ok(id)
+    }
+
+    async fn run_container(&self, id: &String) -> Result<(), DockerError> {
+        self.call(format!("start {}", &id[..12]));
+        self.set_state(id, "running")
+    }
+
+    async fn stop_container(&self, id: &String, timeout: Duration) -> Result<(), DockerError> {
+        self.call(format!("stop {}", &id[..12]));
+        self.timeouts.borrow_mut().push(timeout);
+        self.set_state(id, "exited")
+    }
+
+    async fn remove_container(
+        &self,
+        id: &String,
+        force: bool,
+        volumes: bool,
+    ) -> Result<(), DockerError> {
+        self.call(format!(
+            "remove {} force={} volumes={}",
+            &id[..12],
+            force,
+            volumes
+        ));
+        self.containers.borrow_mut().retain(|c| c.id != *id);
+        Ok(())
+    }
+
+    async fn attach_container(
+        &self,
+        id: &str,
+        _tty: bool,
+        _auto_remove: bool,
+    ) -> Result<u64, DockerError> {
+        self.call(format!("attach {}", &id[..12]));
+        self.set_state(id, "exited")?;
+        Ok(0)
+    }
+
+    async fn health(&self, id: &str) -> Result<Health, DockerError> {
+        let containers = self.containers.borrow();
+
+        match containers.iter().find(|c| c.id == id) {
+            Some(container) if container.state == "running" => Ok(container.health),
+            Some(_) => Ok(Health::Exited(1)),
+            None => Err(DockerError::Inspect(format!("No such container: {}", id))),
+        }
+    }
+
+    async fn inspect_container(&self, id: &str) -> Result<Details, DockerError> {
+        let containers = self.containers.borrow();
+
+        match containers.iter().find(|c| c.id == id) {
+            Some(container) => Ok(Details {
+                created: Utc.timestamp_opt(1666000000, 0).unwrap(),
+                started: Utc.timestamp_opt(1666000060, 0).unwrap(),
+                image_id: container.image_id.clone(),
+            }),
+            None => Err(DockerError::Inspect(format!("No such container: {}", id))),
+        }
+    }
+
+    async fn image_id(&self, tag: &str) -> Result<Option<String>, DockerError> {
+        Ok(self.images.borrow().get(tag).cloned())
+    }
+
+    async fn usage(&self, id: &str) -> Result<Usage, DockerError> {
+        let running = self
+            .containers
+            .borrow()
+            .iter()
+            .any(|c| c.id == id && c.state == "running");
+        if !running {
+            return Err(DockerError::Inspect(format!("No stats for {}", id)));
+        }
+
+        Ok(Usage {
+            cpu: 1.5,
+            memory: 64 * 1024 * 1024,
+        })
+    }
+
+    async fn remove_image(&self, tag: &str) -> Result<(), DockerError> {
+        self.call(format!("remove image {}", tag));
+        self.images.borrow_mut().remove(tag);
+        Ok(())
+    }
+
+    async fn managed_networks(&self) -> Result<Vec<String>, DockerError> {
+        Ok(self.networks.borrow().clone())
+    }
+
+    async fn remove_network(&self, name: &str) -> Result<(), DockerError> {
+        self.call(format!("remove network {}", name));
+        self.networks.borrow_mut().retain(|n| n != name);
+        Ok(())
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<String>, DockerError> {
+        Ok(self.volumes.borrow().clone())
+    }
+
+    async fn remove_volume(&self, name: &str) -> Result<(), DockerError> {
+        self.call(format!("remove volume {}", name));
+        self.volumes.borrow_mut().retain(|v| v != name);
+        Ok(())
+    }
+
+    async fn connect_networks(
+        &self,
+        id: &str,
+        _container: &ContainerConfig,
+        _networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<(), DockerError> {
+        self.call(format!("connect {}", &id[..12]));
+        Ok(())
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<Container>, DockerError> {
+        let containers = self.containers.borrow();
+
+        Ok(containers
+            .iter()
+            .filter(|c| all || c.state == "running")
+            .map(|c| {
+                let mut labels = HashMap::from([("manager", "denver")]);
+                if let Some(hash) = &c.hash {
+                    labels.insert(HASH_LABEL, hash.as_str());
+                }
+
+                serde_json::from_value(json!({
+                    "Id": c.id,
+                    "Names": [format!("/{}", c.name)],
+                    "Image": c.image,
+                    "ImageID": c.image_id,
+                    "Command": "/bin/sh",
+                    "Created": 1666000000,
+                    "Ports": [],
+                    "Labels": labels,
+                    "State": c.state,
+                    "Status": "Up 2 minutes",
+                }))
+                .unwrap()
+            })
+            .collect())
+    }
+}
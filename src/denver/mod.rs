@@ -1,32 +1,145 @@
 use std::fmt::Display;
 use std::path::Path;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
+use futures::future::join_all;
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use regex::Regex;
+use shiplift::rep::Container;
 
 mod completion;
+mod dependencies;
+#[cfg(test)]
+mod fake;
 mod status;
+mod terminal;
 
-use crate::cli::{Cli, Commands, Common, Completion, Run, Status, Stop};
-use crate::config::{read_config, Config, ContainerConfig};
-use crate::docker::{DockerClient, DockerError};
+use crate::cli::{
+    Cli, Column, Commands, Common, Completion, ConfigArgs, ConfigCommands, ConfigFormat, Down,
+    Exec, Logs, Network, NetworkCommands, Restart, Rm, Run, Start, Status, Stop,
+};
+use crate::config::{self, read_config, Config, ConfigError, ContainerConfig};
+use crate::docker::{ContainerEngine, DockerClient, DockerError, Health, HASH_LABEL, STOP_TIMEOUT};
 
 use self::completion::CompletionError;
+use self::terminal::{Confirm, RawMode};
 
-pub struct Denver {
+pub struct Denver<E> {
     config: Config,
-    docker: DockerClient,
+    docker: E,
 }
 
-impl Denver {
-    fn new(config: &str) -> Self {
-        let config = read_config(config);
-        let docker = DockerClient::new(&config);
+impl Denver<DockerClient> {
+    fn new(config: Config) -> Result<Self, DenverError> {
+        let docker = DockerClient::new(&config)?;
 
-        Denver { config, docker }
+        Ok(Denver { config, docker })
     }
 
+    async fn exec(&self, args: &Exec) -> Result<(), DenverError> {
+        let name = &args.container;
+        let config = Self::get_container_config(&self.config, name)?;
+        let container = self.find_container(name, false).await?;
+        let workdir = args.workdir.as_ref().unwrap_or(&config.run.workspace);
+        let tty = terminal::is_tty();
+
+        let raw_mode = RawMode::enable(tty).map_err(|e| DenverError::ExecError(e.to_string()))?;
+        let exit_code = self
+            .docker
+            .exec(&container.id, &args.command, workdir, tty)
+            .await?;
+
+        raw_mode.exit_on_failure(exit_code);
+
+        Ok(())
+    }
+
+    async fn logs(&self, args: &Logs) -> Result<(), DenverError> {
+        // Exited containers are looked up as well, their logs tell why
+        let container = self.find_container(&args.container, true).await?;
+
+        self.docker.logs(&container.id, args).await?;
+
+        Ok(())
+    }
+
+    async fn network(&self, args: &Network) -> Result<(), DenverError> {
+        match args.command {
+            NetworkCommands::Prune => {
+                for name in self.docker.prune_networks().await? {
+                    println!("Removed network {}", name);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn completion(args: &Completion) -> Result<(), DenverError> {
+        completion::completion(args)?;
+        Ok(())
+    }
+
+    fn config(config: &Config, args: &ConfigArgs) -> Result<(), DenverError> {
+        match &args.command {
+            ConfigCommands::Validate => {
+                let mut problems = config::validate(config);
+                let mut names: Vec<&str> = config.containers.keys().map(|k| k.as_str()).collect();
+                names.sort();
+
+                if let Err(e) = dependencies::resolve(config, &names) {
+                    problems.push(e.to_string());
+                }
+
+                // Resolving the endpoint doesn't reach the daemon yet
+                if let Err(e) = DockerClient::new(config) {
+                    problems.push(format!("docker endpoint: {}", DenverError::from(e)));
+                }
+
+                if problems.is_empty() {
+                    println!("Configuration is valid");
+                    return Ok(());
+                }
+
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+
+                Err(DenverError::ConfigError(format!(
+                    "Found {} problem(s) in the configuration",
+                    problems.len()
+                )))
+            }
+            ConfigCommands::Show(args) => {
+                // Kept out of stdout so the output can be used as a config file
+                for source in &config.sources {
+                    eprintln!("Loaded {}", source);
+                }
+
+                if let Some(profile) = &config.profile {
+                    eprintln!("Using profile {}", profile);
+                }
+
+                // Going through serde_json sorts the keys, keeping the output stable
+                let config = serde_json::to_value(config)
+                    .map_err(|e| DenverError::ConfigError(e.to_string()))?;
+
+                let output = match args.format {
+                    ConfigFormat::Yaml => serde_yaml::to_string(&config)
+                        .map_err(|e| DenverError::ConfigError(e.to_string()))?,
+                    ConfigFormat::Json => serde_json::to_string_pretty(&config)
+                        .map_err(|e| DenverError::ConfigError(e.to_string()))?,
+                };
+
+                println!("{}", output.trim_end());
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<E: ContainerEngine> Denver<E> {
     fn get_container_config<'a>(
         config: &'a Config,
         container_name: &String,
@@ -42,47 +155,320 @@ impl Denver {
         }
     }
 
+    // Only running containers are looked at, unless all is set
+    async fn find_container(&self, name: &str, all: bool) -> Result<Container, DenverError> {
+        let containers = self.docker.list_containers(all).await?;
+
+        match containers.into_iter().find(|c| &c.names[0][1..] == name) {
+            Some(container) => Ok(container),
+            None if all => Err(DenverError::UnknownContainer(format!(
+                "{} does not exist, use run to create it",
+                name
+            ))),
+            None => Err(DenverError::UnknownContainer(format!(
+                "{} is not running",
+                name
+            ))),
+        }
+    }
+
     async fn run(&self, args: &Run) -> Result<(), DenverError> {
         let name = &args.common.container;
-        let container = Denver::get_container_config(&self.config, name)?;
+        let order = dependencies::resolve(&self.config, &[name.as_str()])?;
+
+        let running = self.docker.list_containers(false).await?;
+
+        // Dependencies are always started in the background, we only attach
+        // to the container that was explicitly requested.
+        for container in order {
+            let requested = container == name;
+
+            // Running dependencies may be shared with other containers, they
+            // are left alone unless --if-changed finds them outdated
+            let existing = running
+                .iter()
+                .find(|c| &c.names[0][1..] == container.as_str());
+            if let Some(existing) = existing.filter(|_| !requested && !args.if_changed) {
+                println!("{} is already running", container);
+                if args.wait {
+                    self.wait(container, &existing.id).await?;
+                }
+                continue;
+            }
+
+            self.run_container(args, container, requested).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_container(
+        &self,
+        args: &Run,
+        name: &String,
+        attach: bool,
+    ) -> Result<(), DenverError> {
+        let container = Self::get_container_config(&self.config, name)?;
 
         if !args.no_rebuild {
             self.docker.build_image(&args.common, container).await?;
         }
 
-        let running = self.docker.list_containers().await?;
-        let running_container = running.iter().find(|c| &c.names[0][1..] == name.as_str());
+        let existing = self.docker.list_containers(true).await?;
+        let existing_container = existing.iter().find(|c| &c.names[0][1..] == name.as_str());
 
-        if let Some(running_container) = running_container {
-            println!("Removing {}", &running_container.names[0][1..]);
-            self.docker
-                .remove_container(&running_container.id, true)
-                .await?;
+        let up_to_date = match existing_container {
+            Some(existing_container) => self
+                .changes(existing_container, container)
+                .await?
+                .is_empty(),
+            None => false,
+        };
+
+        if let Some(existing_container) = existing_container {
+            if args.if_changed && up_to_date && existing_container.state == "running" {
+                println!("{} is up to date", name);
+                if args.wait {
+                    self.wait(name, &existing_container.id).await?;
+                }
+                return Ok(());
+            }
+        }
+
+        // Stopped containers created from the same configuration and image
+        // are started as they are, keeping any state they had.
+        let reusable = up_to_date
+            && existing_container
+                .map_or(false, |c| matches!(c.state.as_str(), "created" | "exited"));
+
+        let id = match existing_container {
+            Some(existing_container) if reusable => {
+                println!("Reusing {} - {}", &existing_container.id[..12], name);
+                existing_container.id.clone()
+            }
+            _ => {
+                // Stopped containers would clash with the new one's name too
+                if let Some(existing_container) = existing_container {
+                    self.replace_container(name, existing_container).await?;
+                }
+
+                println!("Creating {} with image {}", name, container.tag);
+                let networks = self.config.networks.as_ref();
+                let id = self
+                    .docker
+                    .create_container(name, container, networks)
+                    .await?;
+                self.docker
+                    .connect_networks(&id, container, networks)
+                    .await?;
+                id
+            }
+        };
+
+        if attach && container.run.args.interactive {
+            println!("Attaching to {} - {}", &id[..12], name);
+            let args = &container.run.args;
+            return Self::attach(&self.docker, &id, args.tty, args.auto_remove).await;
         }
 
-        println!("Creating {} with image {}", name, container.tag);
-        let id = self.docker.create_container(name, container).await?;
         self.docker.run_container(&id).await?;
 
         println!("Started {} - {}", &id[..12], name);
 
+        if args.wait {
+            self.wait(name, &id).await?;
+        }
+
+        Ok(())
+    }
+
+    // Health can only be told with a healthcheck, without one there is
+    // nothing to wait for
+    async fn wait(&self, name: &str, id: &str) -> Result<(), DenverError> {
+        let healthcheck = self
+            .config
+            .containers
+            .get(name)
+            .and_then(|c| c.run.healthcheck.as_ref());
+
+        match healthcheck {
+            Some(healthcheck) => self.wait_healthy(name, id, healthcheck.max_wait()).await,
+            None => {
+                println!("{} has no healthcheck, not waiting for it", name);
+                Ok(())
+            }
+        }
+    }
+
+    // Running containers are stopped first, giving them the chance to shut
+    // down cleanly instead of being killed by a forced removal.
+    async fn replace_container(
+        &self,
+        name: &str,
+        container: &Container,
+    ) -> Result<(), DenverError> {
+        let id = &container.id;
+
+        if container.state == "running" {
+            println!("Stopping {} - {}", &id[..12], name);
+            let timeout = self.stop_timeout(name, None);
+            self.docker.stop_container(id, timeout).await?;
+
+            // Containers running with auto remove are already gone by now
+            let remaining = self.docker.list_containers(true).await?;
+            if !remaining.iter().any(|c| &c.id == id) {
+                return Ok(());
+            }
+        }
+
+        println!("Removing {} - {}", &id[..12], name);
+        self.docker.remove_container(id, false, false).await?;
+
+        Ok(())
+    }
+
+    // What changed since the container was created, empty if it's up to date
+    async fn changes(
+        &self,
+        container: &Container,
+        config: &ContainerConfig,
+    ) -> Result<Vec<&'static str>, DenverError> {
+        let networks = self.config.networks.as_ref();
+        let hash = |image_id: &str| {
+            config
+                .hash(image_id, networks)
+                .map_err(DenverError::ConfigError)
+        };
+
+        let latest = self.docker.image_id(&config.tag).await?.unwrap_or_default();
+        let label = container.labels.get(HASH_LABEL);
+        if label == Some(&hash(&latest)?) {
+            return Ok(vec![]);
+        }
+
+        let details = self.docker.inspect_container(&container.id).await?;
+        let mut changes = vec![];
+        if label != Some(&hash(&details.image_id)?) {
+            changes.push("config");
+        }
+        if details.image_id != latest {
+            changes.push("image");
+        }
+
+        Ok(changes)
+    }
+
+    // The flag takes precedence over the container's configuration
+    fn stop_timeout(&self, name: &str, timeout: Option<Duration>) -> Duration {
+        timeout
+            .or_else(|| {
+                self.config
+                    .containers
+                    .get(name)
+                    .and_then(|c| c.run.stop_timeout)
+            })
+            .unwrap_or(STOP_TIMEOUT)
+    }
+
+    async fn wait_healthy(
+        &self,
+        name: &str,
+        id: &str,
+        timeout: Duration,
+    ) -> Result<(), DenverError> {
+        static POLL_INTERVAL: Duration = Duration::from_secs(1);
+        let deadline = Instant::now() + timeout;
+
+        println!("Waiting for {} to become healthy", name);
+
+        loop {
+            // Neither of these is going to get any better by waiting
+            let state = match self.docker.health(id).await? {
+                Health::Healthy => {
+                    println!("{} is healthy", name);
+                    return Ok(());
+                }
+                Health::Unhealthy => {
+                    return Err(DenverError::RunError(format!("{} is unhealthy", name)));
+                }
+                Health::Exited(code) => {
+                    return Err(DenverError::RunError(format!(
+                        "{} exited with code {} before becoming healthy",
+                        name, code
+                    )));
+                }
+                Health::Starting => "starting",
+                Health::Unknown => "unknown",
+            };
+
+            if Instant::now() >= deadline {
+                return Err(DenverError::Timeout(format!(
+                    "{} did not become healthy after {}s, last state: {}",
+                    name,
+                    timeout.as_secs(),
+                    state
+                )));
+            }
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn attach(docker: &E, id: &str, tty: bool, auto_remove: bool) -> Result<(), DenverError> {
+        let tty = tty && terminal::is_tty();
+
+        let raw_mode = RawMode::enable(tty).map_err(|e| DenverError::RunError(e.to_string()))?;
+        let exit_code = docker.attach_container(id, tty, auto_remove).await?;
+
+        raw_mode.exit_on_failure(exit_code);
+
         Ok(())
     }
 
     async fn build(&self, args: &Common) -> Result<(), DenverError> {
-        let name = &args.container;
-        let container = Denver::get_container_config(&self.config, name)?;
+        let order = dependencies::resolve(&self.config, &[args.container.as_str()])?;
 
-        self.docker.build_image(args, container).await?;
+        for name in order {
+            let container = Self::get_container_config(&self.config, name)?;
+
+            self.docker.build_image(args, container).await?;
+        }
 
         Ok(())
     }
 
     async fn status(&self, args: &Status) -> Result<(), DenverError> {
-        static EMPTY_ID: &str = "------------";
-        let containers = self.docker.list_containers().await?;
+        // Stopped containers can still be started or removed, so they are
+        // listed too
+        let containers = self.docker.list_containers(true).await?;
         let re = Regex::new(&args.pattern)?;
-        let mut lines = status::Containers::new();
+
+        let output = self
+            .status_table(&containers, &re, &args.columns)
+            .await?
+            .render(&args.format)
+            .map_err(DenverError::StatusError)?;
+        print!("{}", output);
+
+        Ok(())
+    }
+
+    async fn status_table<'a>(
+        &'a self,
+        containers: &'a [Container],
+        re: &Regex,
+        columns: &[Column],
+    ) -> Result<status::Containers<'a>, DenverError> {
+        let inspect = columns
+            .iter()
+            .any(|c| matches!(c, Column::Created | Column::Uptime | Column::Stale));
+        let stale = columns.contains(&Column::Stale);
+        let outdated = columns.contains(&Column::Outdated);
+        let usage = columns
+            .iter()
+            .any(|c| matches!(c, Column::Cpu | Column::Memory));
+        let mut lines = status::Containers::new(columns);
+        let mut ids = vec![];
 
         // We first print all created containers
         for container in containers.iter().filter(|c| {
@@ -90,14 +476,59 @@ impl Denver {
             re.is_match(name)
         }) {
             let name = &container.names[0][1..];
+            let config = self.config.containers.get(name);
 
-            lines.push(status::Container::new(
-                &container.id[..12],
+            let mut line = status::Container::new(
+                Some(&container.id),
                 name,
                 &container.image,
                 &container.state,
                 &container.status,
-            ));
+                status::format_ports(&container.ports),
+                config.and_then(|c| c.source.as_deref()),
+            );
+
+            if inspect {
+                let details = self.docker.inspect_container(&container.id).await?;
+                line.created = Some(details.created);
+                line.started = Some(details.started);
+
+                if let Some(config) = config.filter(|_| stale) {
+                    // Nothing to compare against if the tag was never built
+                    line.stale = self
+                        .docker
+                        .image_id(&config.tag)
+                        .await?
+                        .map(|id| id != details.image_id);
+                }
+            }
+
+            if let Some(config) = config.filter(|_| outdated) {
+                line.outdated = Some(self.changes(container, config).await?);
+            }
+
+            lines.push(line);
+            // Only running containers have any usage to sample
+            ids.push((container.state == "running").then_some(container.id.as_str()));
+        }
+
+        // Sampling usage takes a while, so it's done for all containers at
+        // once. Containers whose usage can't be read are left blank.
+        if usage {
+            let usages = join_all(ids.iter().map(|id| async move {
+                match id {
+                    Some(id) => self.docker.usage(id).await.ok(),
+                    None => None,
+                }
+            }))
+            .await;
+
+            for (line, usage) in lines.data.iter_mut().zip(usages) {
+                if let Some(usage) = usage {
+                    line.cpu = Some(usage.cpu);
+                    line.memory = Some(usage.memory);
+                }
+            }
         }
 
         // And now we can print any containers that are not created
@@ -113,44 +544,244 @@ impl Denver {
                 .any(|c| c == name)
             {
                 lines.push(status::Container::new(
-                    EMPTY_ID,
+                    None,
                     name,
                     &config.tag,
-                    "NOT CREATED",
+                    "not created",
                     "",
+                    vec![],
+                    config.source.as_deref(),
                 ));
             }
         }
 
-        print!("{}", lines);
+        Ok(lines)
+    }
+
+    async fn start(&self, args: &Start) -> Result<(), DenverError> {
+        let container = self.find_container(&args.container, true).await?;
+        let id = &container.id;
+
+        if container.state == "running" {
+            println!("{} is already running", args.container);
+            return Ok(());
+        }
+
+        self.docker.run_container(id).await?;
+        println!("Started {} - {}", &id[..12], args.container);
+
+        Ok(())
+    }
+
+    async fn restart(&self, args: &Restart) -> Result<(), DenverError> {
+        let container = self.find_container(&args.container, true).await?;
+        let id = &container.id;
+
+        if container.state == "running" {
+            println!("Stopping {} - {}", &id[..12], args.container);
+            let timeout = self.stop_timeout(&args.container, args.timeout);
+            self.docker.stop_container(id, timeout).await?;
+        }
+
+        self.docker.run_container(id).await?;
+        println!("Started {} - {}", &id[..12], args.container);
 
         Ok(())
     }
 
     async fn stop(&self, args: &Stop) -> Result<(), DenverError> {
-        let containers = self.docker.list_containers().await?;
+        let containers = self.docker.list_containers(false).await?;
         let re = Regex::new(&args.pattern)?;
 
-        for container in &containers {
-            let name = &container.names[0][1..];
+        let matches: Vec<&str> = containers
+            .iter()
+            .map(|c| &c.names[0][1..])
+            .filter(|name| re.is_match(name))
+            .collect();
+
+        // Containers in the configuration take their dependencies down with
+        // them, dependents go first.
+        let configured: Vec<&str> = matches
+            .iter()
+            .copied()
+            .filter(|name| self.config.containers.contains_key(*name))
+            .collect();
+        let mut order: Vec<&str> = dependencies::resolve(&self.config, &configured)?
+            .into_iter()
+            .map(|name| name.as_str())
+            .rev()
+            .collect();
 
-            if re.is_match(name) {
+        order.extend(matches.iter().filter(|name| !configured.contains(*name)));
+
+        let mut stopped = vec![];
+        for name in order {
+            if let Some(container) = containers.iter().find(|c| &c.names[0][1..] == name) {
                 println!("Stopping {} - {}", &container.id[..12], name);
-                self.docker.stop_container(&container.id).await?;
+                let timeout = self.stop_timeout(name, args.timeout);
+                self.docker.stop_container(&container.id, timeout).await?;
+                stopped.push((name, &container.id));
+            }
+        }
+
+        if args.rm {
+            // Containers running with auto remove are already gone by now
+            let remaining = self.docker.list_containers(true).await?;
+
+            // Dependencies stopped along the way may be shared with other
+            // containers, only the ones asked for are removed.
+            for (name, id) in stopped
+                .into_iter()
+                .filter(|(name, _)| matches.contains(name))
+            {
+                if remaining.iter().any(|c| &c.id == id) {
+                    println!("Removing {} - {}", &id[..12], name);
+                    self.docker.remove_container(id, false, false).await?;
+                }
             }
         }
 
         Ok(())
     }
 
-    fn completion(args: &Completion) -> Result<(), DenverError> {
-        completion::completion(args)?;
+    async fn rm(&self, args: &Rm, confirm: Option<Confirm>) -> Result<(), DenverError> {
+        let containers = self.docker.list_containers(true).await?;
+        let re = Regex::new(&args.pattern)?;
+
+        let mut matches = vec![];
+        for container in containers.iter().filter(|c| re.is_match(&c.names[0][1..])) {
+            let name = &container.names[0][1..];
+
+            if container.state == "running" && !args.force {
+                println!("Skipping {}, it is running. Use --force to remove it", name);
+                continue;
+            }
+
+            matches.push((name, container));
+        }
+
+        if matches.is_empty() {
+            println!("No containers to remove");
+            return Ok(());
+        }
+
+        if !args.yes {
+            let confirm = match confirm {
+                Some(confirm) => confirm,
+                None => {
+                    return Err(DenverError::RemoveError(
+                        "Cannot ask for confirmation without a terminal, pass --yes to remove the containers"
+                            .to_string(),
+                    ))
+                }
+            };
+
+            println!("The following containers will be removed:");
+            for (name, container) in &matches {
+                println!("  {} - {}", &container.id[..12], name);
+            }
+
+            let confirmed =
+                confirm("Continue?").map_err(|e| DenverError::RemoveError(e.to_string()))?;
+            if !confirmed {
+                return Ok(());
+            }
+        }
+
+        for (name, container) in matches {
+            println!("Removing {} - {}", &container.id[..12], name);
+            self.docker
+                .remove_container(&container.id, args.force, args.volumes)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, args: &Down) -> Result<(), DenverError> {
+        let action = if args.dry_run {
+            "Would remove"
+        } else {
+            "Removing"
+        };
+
+        // Dependencies are not looked at, so a broken configuration can
+        // still be cleaned up
+        if args.dry_run {
+            for container in self.docker.list_containers(true).await? {
+                let name = &container.names[0][1..];
+                println!("{} {} - {}", action, &container.id[..12], name);
+            }
+        } else {
+            for container in self.docker.list_containers(false).await? {
+                let name = &container.names[0][1..];
+                println!("Stopping {} - {}", &container.id[..12], name);
+                let timeout = self.stop_timeout(name, None);
+                self.docker.stop_container(&container.id, timeout).await?;
+            }
+
+            // Containers running with auto remove are already gone by now
+            for container in self.docker.list_containers(true).await? {
+                let name = &container.names[0][1..];
+                println!("{} {} - {}", action, &container.id[..12], name);
+                self.docker
+                    .remove_container(&container.id, false, false)
+                    .await?;
+            }
+        }
+
+        let mut containers: Vec<&ContainerConfig> = self.config.containers.values().collect();
+        containers.sort_by_key(|c| &c.tag);
+
+        if args.images {
+            let mut tags: Vec<&str> = containers.iter().map(|c| c.tag.as_str()).collect();
+            tags.dedup();
+
+            for tag in tags {
+                if self.docker.image_id(tag).await?.is_none() {
+                    continue;
+                }
+
+                println!("{} image {}", action, tag);
+                if !args.dry_run {
+                    self.docker.remove_image(tag).await?;
+                }
+            }
+        }
+
+        if args.networks {
+            for network in self.docker.managed_networks().await? {
+                println!("{} network {}", action, network);
+                if !args.dry_run {
+                    self.docker.remove_network(&network).await?;
+                }
+            }
+        }
+
+        if args.volumes {
+            let existing = self.docker.list_volumes().await?;
+            let mut volumes: Vec<&str> = containers
+                .iter()
+                .flat_map(|c| c.named_volumes())
+                .filter(|v| existing.iter().any(|e| e == v))
+                .collect();
+            volumes.sort();
+            volumes.dedup();
+
+            for volume in volumes {
+                println!("{} volume {}", action, volume);
+                if !args.dry_run {
+                    self.docker.remove_volume(volume).await?;
+                }
+            }
+        }
+
         Ok(())
     }
 
     async fn watch(&mut self, args: &Run) -> Result<(), DenverError> {
         let name = &args.common.container;
-        let container = Denver::get_container_config(&self.config, name)?;
+        let container = Self::get_container_config(&self.config, name)?;
         let context = &container.build.context;
         let (tx, mut rx) = tokio::sync::mpsc::channel(100);
         let rt = tokio::runtime::Handle::current();
@@ -188,6 +819,7 @@ impl Denver {
     }
 }
 
+#[derive(Debug)]
 pub enum DenverError {
     UnknownContainer(String),
     BuildError(String),
@@ -197,6 +829,13 @@ pub enum DenverError {
     StatusError(String),
     InvalidRegex(String),
     CompletionError(String),
+    ExecError(String),
+    LogsError(String),
+    DependencyCycle(String),
+    Timeout(String),
+    NetworkError(String),
+    ConfigError(String),
+    ConnectionError(String),
 }
 
 impl Display for DenverError {
@@ -209,6 +848,13 @@ impl Display for DenverError {
             | DenverError::RunError(e)
             | DenverError::StopError(e)
             | DenverError::RemoveError(e)
+            | DenverError::ExecError(e)
+            | DenverError::LogsError(e)
+            | DenverError::DependencyCycle(e)
+            | DenverError::Timeout(e)
+            | DenverError::NetworkError(e)
+            | DenverError::ConfigError(e)
+            | DenverError::ConnectionError(e)
             | DenverError::BuildError(e) => {
                 write!(f, "{}", e)
             }
@@ -219,15 +865,25 @@ impl Display for DenverError {
 impl From<DockerError> for DenverError {
     fn from(e: DockerError) -> Self {
         match e {
-            DockerError::List(e) => DenverError::StatusError(e),
+            DockerError::Connect(e) => DenverError::ConnectionError(e),
+            DockerError::List(e) | DockerError::Inspect(e) => DenverError::StatusError(e),
             DockerError::Build(e) => DenverError::BuildError(e),
             DockerError::Run(e) => DenverError::RunError(e),
             DockerError::Stop(e) => DenverError::StopError(e),
             DockerError::Remove(e) => DenverError::RemoveError(e),
+            DockerError::Exec(e) => DenverError::ExecError(e),
+            DockerError::Logs(e) => DenverError::LogsError(e),
+            DockerError::Network(e) => DenverError::NetworkError(e),
         }
     }
 }
 
+impl From<ConfigError> for DenverError {
+    fn from(e: ConfigError) -> Self {
+        DenverError::ConfigError(e.to_string())
+    }
+}
+
 impl From<regex::Error> for DenverError {
     fn from(e: regex::Error) -> Self {
         DenverError::InvalidRegex(e.to_string())
@@ -250,20 +906,723 @@ impl From<notify::Error> for DenverError {
     }
 }
 
-pub async fn run(cli: Cli) {
-    let mut denver = Denver::new(&cli.config);
+pub fn run(cli: Cli) {
+    let result = match read_config(cli.config.as_deref(), cli.profile.as_deref()) {
+        Ok(config) => {
+            DockerClient::export_tls(&config);
 
-    let result = match cli.command {
+            tokio::runtime::Runtime::new()
+                .expect("Failed to start the tokio runtime")
+                .block_on(dispatch(cli.command, config))
+        }
+        Err(e) => Err(DenverError::from(e)),
+    };
+
+    if let Err(e) = result {
+        println!("Error: {}", e);
+    }
+}
+
+async fn dispatch(command: Commands, config: Config) -> Result<(), DenverError> {
+    // Commands working only with the configuration don't need a daemon, or
+    // even a valid endpoint to reach it
+    match &command {
+        Commands::Completion(args) => return Denver::<DockerClient>::completion(args),
+        Commands::Config(args) => return Denver::<DockerClient>::config(&config, args),
+        _ => {}
+    }
+
+    let mut denver = Denver::new(config)?;
+    denver.docker.ping().await?;
+
+    match command {
         Commands::Run(args) => denver.run(&args).await,
         Commands::Build(args) => denver.build(&args.common).await,
         Commands::Status(args) => denver.status(&args).await,
+        Commands::Start(args) => denver.start(&args).await,
+        Commands::Restart(args) => denver.restart(&args).await,
         Commands::Stop(args) => denver.stop(&args).await,
-        Commands::Completion(args) => Denver::completion(&args),
+        Commands::Rm(args) => denver.rm(&args, terminal::prompt()).await,
+        Commands::Down(args) => denver.down(&args).await,
         Commands::Watch(args) => denver.watch(&args).await,
-    };
+        Commands::Exec(args) => denver.exec(&args).await,
+        Commands::Logs(args) => denver.logs(&args).await,
+        Commands::Network(args) => denver.network(&args).await,
+        Commands::Completion(_) | Commands::Config(_) => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fake::FakeEngine;
+    use super::*;
+
+    const COLUMNS: [Column; 6] = [
+        Column::Id,
+        Column::Name,
+        Column::Image,
+        Column::State,
+        Column::Status,
+        Column::Ports,
+    ];
+
+    fn denver(engine: FakeEngine) -> Denver<FakeEngine> {
+        let config = Config::new(
+            r#"
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+    depends_on: [db]
+  db:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/db:tag
+"#,
+        )
+        .unwrap();
+
+        Denver {
+            config,
+            docker: engine,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_recreates_container() {
+        let denver = denver(FakeEngine::with_running(&[("app", "quay.io/org/app:old")]));
+        let old = denver.docker.id_of("app").unwrap();
+        let args = Run {
+            common: Common {
+                container: "app".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: false,
+            wait: false,
+            if_changed: false,
+        };
+
+        denver.run(&args).await.unwrap();
+
+        let db = denver.docker.id_of("db").unwrap();
+        let app = denver.docker.id_of("app").unwrap();
+        assert_ne!(old, app);
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                "build quay.io/org/db:tag".to_string(),
+                "create db".to_string(),
+                format!("connect {}", &db[..12]),
+                format!("start {}", &db[..12]),
+                "build quay.io/org/app:tag".to_string(),
+                format!("stop {}", &old[..12]),
+                format!("remove {} force=false volumes=false", &old[..12]),
+                "create app".to_string(),
+                format!("connect {}", &app[..12]),
+                format!("start {}", &app[..12]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_keeps_running_dependencies() {
+        let denver = denver(FakeEngine::with_running(&[("db", "quay.io/org/db:tag")]));
+        let db = denver.docker.id_of("db").unwrap();
+        let mut args = Run {
+            common: Common {
+                container: "app".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: false,
+            wait: false,
+            if_changed: false,
+        };
+
+        denver.run(&args).await.unwrap();
+
+        let app = denver.docker.id_of("app").unwrap();
+        assert_eq!(denver.docker.id_of("db").unwrap(), db);
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                "build quay.io/org/app:tag".to_string(),
+                "create app".to_string(),
+                format!("connect {}", &app[..12]),
+                format!("start {}", &app[..12]),
+            ]
+        );
+
+        // db was not created by denver, so it is outdated
+        args.if_changed = true;
+        denver.run(&args).await.unwrap();
+        assert_ne!(denver.docker.id_of("db").unwrap(), db);
+    }
+
+    #[tokio::test]
+    async fn test_status_not_created() {
+        let denver = denver(FakeEngine::with_running(&[
+            ("db", "quay.io/org/db:tag"),
+            ("other", "fedora:36"),
+        ]));
+        let containers = denver.docker.list_containers(false).await.unwrap();
+
+        let re = Regex::new(".*").unwrap();
+        let table = denver
+            .status_table(&containers, &re, &COLUMNS)
+            .await
+            .unwrap()
+            .to_string();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(lines.len(), 4, "{}", table);
+        assert!(lines[0].starts_with("CONTAINER ID"));
+        assert!(lines[1].contains("db") && lines[1].contains("RUNNING"));
+        assert!(lines[2].contains("other") && lines[2].contains("RUNNING"));
+        assert!(lines[3].starts_with("------------"));
+        assert!(lines[3].contains("app"));
+        assert!(lines[3].contains("quay.io/org/app:tag"));
+        assert!(lines[3].contains("NOT CREATED"));
+
+        let re = Regex::new("^db$").unwrap();
+        let table = denver
+            .status_table(&containers, &re, &COLUMNS)
+            .await
+            .unwrap()
+            .to_string();
+        assert_eq!(table.lines().count(), 2, "{}", table);
+        assert!(!table.contains("NOT CREATED"));
+    }
+
+    #[tokio::test]
+    async fn test_status_stopped() {
+        let denver = denver(FakeEngine::with_running(&[("db", "quay.io/org/db:tag")]));
+        let db = denver.docker.id_of("db").unwrap();
+        denver
+            .docker
+            .stop_container(&db, Duration::from_secs(1))
+            .await
+            .unwrap();
+        let containers = denver.docker.list_containers(true).await.unwrap();
+
+        let re = Regex::new("^db$").unwrap();
+        let table = denver
+            .status_table(&containers, &re, &COLUMNS)
+            .await
+            .unwrap()
+            .to_string();
+        assert!(table.contains("EXITED"), "{}", table);
+        assert!(!table.contains("NOT CREATED"), "{}", table);
+    }
+
+    #[tokio::test]
+    async fn test_status_details() {
+        let denver = denver(FakeEngine::with_running(&[
+            ("app", "quay.io/org/app:tag"),
+            ("db", "quay.io/org/db:tag"),
+        ]));
+        let containers = denver.docker.list_containers(false).await.unwrap();
+        let common = Common {
+            container: "app".to_string(),
+            no_cache: false,
+        };
+        let app = &denver.config.containers["app"];
+        denver.docker.build_image(&common, app).await.unwrap();
+
+        let re = Regex::new(".*").unwrap();
+        let table = denver
+            .status_table(
+                &containers,
+                &re,
+                &[Column::Name, Column::Stale, Column::Memory],
+            )
+            .await
+            .unwrap()
+            .to_string();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(
+            lines[0].split_whitespace().collect::<Vec<_>>(),
+            vec!["NAME", "STALE", "MEMORY"]
+        );
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            vec!["app", "yes", "64.0MiB"]
+        );
+        assert_eq!(
+            lines[2].split_whitespace().collect::<Vec<_>>(),
+            vec!["db", "no", "64.0MiB"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_status_usage_unavailable() {
+        let denver = denver(FakeEngine::with_running(&[
+            ("app", "quay.io/org/app:tag"),
+            ("db", "quay.io/org/db:tag"),
+        ]));
+        let db = denver.docker.id_of("db").unwrap();
+        denver
+            .docker
+            .stop_container(&db, Duration::from_secs(1))
+            .await
+            .unwrap();
+        let containers = denver.docker.list_containers(true).await.unwrap();
+
+        let re = Regex::new(".*").unwrap();
+        let table = denver
+            .status_table(&containers, &re, &[Column::Name, Column::Memory])
+            .await
+            .unwrap()
+            .to_string();
+        let lines: Vec<&str> = table.lines().collect();
+
+        assert_eq!(
+            lines[1].split_whitespace().collect::<Vec<_>>(),
+            vec!["app", "64.0MiB"]
+        );
+        assert_eq!(lines[2].split_whitespace().collect::<Vec<_>>(), vec!["db"]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_rm() {
+        let denver = denver(FakeEngine::with_running(&[
+            ("app", "quay.io/org/app:tag"),
+            ("db", "quay.io/org/db:tag"),
+        ]));
+        let app = denver.docker.id_of("app").unwrap();
+        let db = denver.docker.id_of("db").unwrap();
+        let args = Stop {
+            pattern: "^app$".to_string(),
+            rm: true,
+            timeout: None,
+        };
+
+        denver.stop(&args).await.unwrap();
+
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                format!("stop {}", &app[..12]),
+                format!("stop {}", &db[..12]),
+                format!("remove {} force=false volumes=false", &app[..12]),
+            ]
+        );
+        assert!(denver.docker.id_of("app").is_none());
+        assert_eq!(denver.docker.id_of("db").unwrap(), db);
+    }
+
+    #[tokio::test]
+    async fn test_rm() {
+        let denver = denver(FakeEngine::with_running(&[
+            ("app", "quay.io/org/app:tag"),
+            ("db", "quay.io/org/db:tag"),
+        ]));
+        let app = denver.docker.id_of("app").unwrap();
+        let db = denver.docker.id_of("db").unwrap();
+        denver
+            .docker
+            .stop_container(&db, STOP_TIMEOUT)
+            .await
+            .unwrap();
+
+        let mut args = Rm {
+            pattern: ".*".to_string(),
+            force: false,
+            volumes: true,
+            yes: false,
+        };
+
+        // Nobody can confirm without a terminal
+        assert!(matches!(
+            denver.rm(&args, None).await,
+            Err(DenverError::RemoveError(_))
+        ));
+        assert!(denver.docker.calls()[1..].is_empty());
+
+        denver.rm(&args, Some(|_| Ok(false))).await.unwrap();
+        assert!(denver.docker.calls()[1..].is_empty());
+
+        denver.rm(&args, Some(|_| Ok(true))).await.unwrap();
+
+        // Running containers are left alone without --force
+        assert_eq!(
+            denver.docker.calls()[1..],
+            [format!("remove {} force=false volumes=true", &db[..12])]
+        );
+        assert!(denver.docker.id_of("app").is_some());
+
+        args.force = true;
+        args.yes = true;
+        denver.rm(&args, None).await.unwrap();
+
+        assert_eq!(
+            denver.docker.calls()[2..],
+            [format!("remove {} force=true volumes=true", &app[..12])]
+        );
+        assert!(denver.docker.id_of("app").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_down() {
+        let config = Config::new(
+            r#"
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        volumes: [cache:/root/.cache, /dev:/dev]
+    tag: quay.io/org/app:tag
+  db:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        volumes: [data:/var/lib/db]
+    tag: quay.io/org/db:tag
+"#,
+        )
+        .unwrap();
+        let docker = FakeEngine::with_running(&[("app", "quay.io/org/app:tag")]);
+        docker.add_network("denver");
+        docker.add_volume("cache");
+        docker.add_volume("unrelated");
+        let denver = Denver { config, docker };
+        let app = denver.docker.id_of("app").unwrap();
+
+        let mut args = Down {
+            images: true,
+            networks: true,
+            volumes: true,
+            dry_run: true,
+        };
+        denver.down(&args).await.unwrap();
+        assert!(denver.docker.calls().is_empty());
+
+        args.dry_run = false;
+        denver.down(&args).await.unwrap();
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                format!("stop {}", &app[..12]),
+                format!("remove {} force=false volumes=false", &app[..12]),
+                "remove image quay.io/org/app:tag".to_string(),
+                "remove network denver".to_string(),
+                "remove volume cache".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_down_broken_dependencies() {
+        let config = Config::new(
+            r#"
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+    depends_on: [missing]
+"#,
+        )
+        .unwrap();
+        let docker = FakeEngine::with_running(&[("app", "quay.io/org/app:tag")]);
+        let denver = Denver { config, docker };
+        let app = denver.docker.id_of("app").unwrap();
+
+        let args = Down {
+            images: false,
+            networks: false,
+            volumes: false,
+            dry_run: false,
+        };
+        denver.down(&args).await.unwrap();
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                format!("stop {}", &app[..12]),
+                format!("remove {} force=false volumes=false", &app[..12]),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_reuses_stopped_container() {
+        let mut denver = denver(FakeEngine::default());
+        let args = Run {
+            common: Common {
+                container: "db".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: true,
+            wait: false,
+            if_changed: false,
+        };
+        denver.docker.tag("quay.io/org/db:tag");
+
+        denver.run(&args).await.unwrap();
+        let db = denver.docker.id_of("db").unwrap();
+        denver
+            .docker
+            .stop_container(&db, STOP_TIMEOUT)
+            .await
+            .unwrap();
+
+        // Same configuration, the container is started again
+        denver.run(&args).await.unwrap();
+        assert_eq!(denver.docker.id_of("db").unwrap(), db);
+        assert_eq!(denver.docker.calls()[4..], [format!("start {}", &db[..12])]);
+
+        // Running containers are always recreated
+        denver.run(&args).await.unwrap();
+        let recreated = denver.docker.id_of("db").unwrap();
+        assert_ne!(recreated, db);
+        assert_eq!(
+            denver.docker.calls()[5..7],
+            [
+                format!("stop {}", &db[..12]),
+                format!("remove {} force=false volumes=false", &db[..12])
+            ]
+        );
+
+        // So are stopped containers with an outdated configuration
+        denver
+            .docker
+            .stop_container(&recreated, STOP_TIMEOUT)
+            .await
+            .unwrap();
+        denver
+            .config
+            .containers
+            .get_mut("db")
+            .unwrap()
+            .run
+            .workspace = "/other".to_string();
+
+        denver.run(&args).await.unwrap();
+        assert_ne!(denver.docker.id_of("db").unwrap(), recreated);
+    }
+
+    #[tokio::test]
+    async fn test_run_if_changed() {
+        let denver = denver(FakeEngine::default());
+        let args = Run {
+            common: Common {
+                container: "db".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: true,
+            wait: false,
+            if_changed: true,
+        };
+        denver.docker.tag("quay.io/org/db:tag");
+
+        denver.run(&args).await.unwrap();
+        let db = denver.docker.id_of("db").unwrap();
+        let calls = denver.docker.calls().len();
+
+        // Nothing changed, the running container is left alone
+        denver.run(&args).await.unwrap();
+        assert_eq!(denver.docker.id_of("db").unwrap(), db);
+        assert_eq!(denver.docker.calls().len(), calls);
+
+        // A new image for the tag means recreating it
+        denver.docker.tag("quay.io/org/db:tag");
+        denver.run(&args).await.unwrap();
+        assert_ne!(denver.docker.id_of("db").unwrap(), db);
+        assert_eq!(denver.docker.calls()[calls], format!("stop {}", &db[..12]));
+    }
+
+    #[tokio::test]
+    async fn test_status_outdated() {
+        let denver = denver(FakeEngine::with_running(&[("app", "quay.io/org/app:tag")]));
+        let args = Run {
+            common: Common {
+                container: "db".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: true,
+            wait: false,
+            if_changed: false,
+        };
+        denver.docker.tag("quay.io/org/db:tag");
+        denver.run(&args).await.unwrap();
+        denver.docker.tag("quay.io/org/app:tag");
+
+        let re = Regex::new(".*").unwrap();
+        let outdated = |table: String| -> Vec<String> {
+            table
+                .lines()
+                .skip(1)
+                .map(|l| l.split_whitespace().skip(1).collect::<Vec<_>>().join(" "))
+                .collect()
+        };
+
+        let containers = denver.docker.list_containers(false).await.unwrap();
+        let table = denver
+            .status_table(&containers, &re, &[Column::Name, Column::Outdated])
+            .await
+            .unwrap()
+            .to_string();
+        assert_eq!(outdated(table), vec!["config, image", "no"]);
+
+        denver.docker.tag("quay.io/org/db:tag");
+        let table = denver
+            .status_table(&containers, &re, &[Column::Name, Column::Outdated])
+            .await
+            .unwrap()
+            .to_string();
+        assert_eq!(outdated(table), vec!["config, image", "image"]);
+    }
+
+    #[tokio::test]
+    async fn test_stop_timeout() {
+        let mut denver = denver(FakeEngine::with_running(&[
+            ("app", "quay.io/org/app:tag"),
+            ("db", "quay.io/org/db:tag"),
+        ]));
+        denver
+            .config
+            .containers
+            .get_mut("db")
+            .unwrap()
+            .run
+            .stop_timeout = Some(Duration::from_secs(60));
+        let mut args = Stop {
+            pattern: ".*".to_string(),
+            rm: false,
+            timeout: None,
+        };
+
+        denver.stop(&args).await.unwrap();
+        assert_eq!(
+            denver.docker.timeouts(),
+            vec![STOP_TIMEOUT, Duration::from_secs(60)]
+        );
+
+        args.timeout = Some(Duration::from_secs(1));
+        let app = denver.docker.id_of("app").unwrap();
+        denver.docker.run_container(&app).await.unwrap();
+        denver.stop(&args).await.unwrap();
+        assert_eq!(denver.docker.timeouts()[2..], [Duration::from_secs(1)]);
+    }
+
+    #[test]
+    fn test_validate_endpoint() {
+        let config = Config::new("socket: ssh://docker.local\ncontainers: {}").unwrap();
+        let args = ConfigArgs {
+            command: ConfigCommands::Validate,
+        };
+
+        // An unusable endpoint is a problem in the configuration, not a
+        // reason to fail before validating it
+        assert!(matches!(
+            Denver::<DockerClient>::config(&config, &args),
+            Err(DenverError::ConfigError(e)) if e.contains("1 problem")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_wait_healthy() {
+        let denver = denver(FakeEngine::with_running(&[("db", "quay.io/org/db:tag")]));
+        let db = denver.docker.id_of("db").unwrap();
+        let timeout = Duration::from_secs(60);
+
+        denver.wait_healthy("db", &db, timeout).await.unwrap();
+
+        // Fails right away rather than waiting out the timeout
+        denver.docker.set_health("db", Health::Unhealthy);
+        assert!(matches!(
+            denver.wait_healthy("db", &db, timeout).await,
+            Err(DenverError::RunError(e)) if e.contains("unhealthy")
+        ));
+
+        denver
+            .docker
+            .stop_container(&db, STOP_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(matches!(
+            denver.wait_healthy("db", &db, timeout).await,
+            Err(DenverError::RunError(e)) if e.contains("exited")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_run_waits_for_running_dependencies() {
+        let config = Config::new(
+            r#"
+containers:
+  app:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+    tag: quay.io/org/app:tag
+    depends_on: [db]
+  db:
+    build:
+        context: ctx/
+    run:
+        workspace: /some/path/
+        healthcheck:
+            command: pg_isready -U postgres
+    tag: quay.io/org/db:tag
+"#,
+        )
+        .unwrap();
+        let docker = FakeEngine::with_running(&[("db", "quay.io/org/db:tag")]);
+        docker.set_health("db", Health::Unhealthy);
+        let denver = Denver { config, docker };
+        let args = Run {
+            common: Common {
+                container: "app".to_string(),
+                no_cache: false,
+            },
+            no_rebuild: false,
+            wait: true,
+            if_changed: false,
+        };
+
+        assert!(matches!(
+            denver.run(&args).await,
+            Err(DenverError::RunError(e)) if e.contains("db is unhealthy")
+        ));
+        assert!(denver.docker.calls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restart() {
+        let denver = denver(FakeEngine::with_running(&[("app", "quay.io/org/app:tag")]));
+        let app = denver.docker.id_of("app").unwrap();
+        let args = Restart {
+            container: "app".to_string(),
+            timeout: Some(Duration::from_secs(30)),
+        };
+
+        denver.restart(&args).await.unwrap();
+        assert_eq!(
+            denver.docker.calls(),
+            vec![
+                format!("stop {}", &app[..12]),
+                format!("start {}", &app[..12])
+            ]
+        );
 
-    match result {
-        Ok(()) => {}
-        Err(e) => println!("Error: {}", e),
+        let args = Start {
+            container: "db".to_string(),
+        };
+        assert!(matches!(
+            denver.start(&args).await,
+            Err(DenverError::UnknownContainer(_))
+        ));
     }
 }
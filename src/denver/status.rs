@@ -1,85 +1,141 @@
 use std::cmp::max;
 use std::fmt::Display;
 
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::Serialize;
+use shiplift::rep::Port;
+
+use crate::cli::{Column, StatusFormat};
+
 const PADDING: usize = 2;
 
+// Shown in place of the ID of containers that are not created
+const EMPTY_ID: &str = "------------";
+
 pub struct Containers<'a> {
     pub data: Vec<Container<'a>>,
+    columns: Vec<Column>,
 }
 
 impl<'a> Containers<'a> {
-    pub fn new() -> Self {
+    pub fn new(columns: &[Column]) -> Self {
         Containers {
-            data: vec![Container::new(
-                "CONTAINER ID",
-                "NAME",
-                "IMAGE",
-                "STATE",
-                "STATUS",
-            )],
+            data: vec![],
+            columns: columns.to_vec(),
         }
     }
 
-    fn find_lengths(&self) -> (usize, usize, usize, usize) {
-        let mut name = 0;
-        let mut image = 0;
-        let mut state = 0;
-        let mut status = 0;
-
-        for data in &self.data {
-            name = max(data.name.len(), name);
-            image = max(data.image.len(), image);
-            state = max(data.state.len(), state);
-            status = max(data.status.len(), status);
+    fn header(column: Column) -> &'static str {
+        match column {
+            Column::Id => "CONTAINER ID",
+            Column::Name => "NAME",
+            Column::Image => "IMAGE",
+            Column::State => "STATE",
+            Column::Status => "STATUS",
+            Column::Ports => "PORTS",
+            Column::Created => "CREATED",
+            Column::Uptime => "UPTIME",
+            Column::Stale => "STALE",
+            Column::Outdated => "OUTDATED",
+            Column::Cpu => "CPU %",
+            Column::Memory => "MEMORY",
         }
-        (
-            name + PADDING,
-            image + PADDING,
-            state + PADDING,
-            status + PADDING,
-        )
     }
 
     pub fn push(&mut self, c: Container<'a>) {
         self.data.push(c);
     }
+
+    pub fn render(&self, format: &StatusFormat) -> Result<String, String> {
+        match format {
+            StatusFormat::Table => Ok(self.to_string()),
+            StatusFormat::Json => serde_json::to_string_pretty(&self.data)
+                .map(|json| json + "\n")
+                .map_err(|e| e.to_string()),
+            StatusFormat::Yaml => serde_yaml::to_string(&self.data).map_err(|e| e.to_string()),
+            StatusFormat::Template(template) => self
+                .data
+                .iter()
+                .map(|c| c.render(template).map(|line| line + "\n"))
+                .collect(),
+        }
+    }
 }
 
 impl<'a> Display for Containers<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (name, image, state, status) = self.find_lengths();
-        let id = 12 + PADDING;
-
-        for line in &self.data {
-            writeln!(
-                f,
-                "{:id$}{:name$}{:image$}{:state$}{:status$}",
-                line.id,
-                line.name,
-                line.image,
-                line.state.to_uppercase(),
-                line.status
-            )?;
+        let header = self
+            .columns
+            .iter()
+            .map(|c| Self::header(*c).to_string())
+            .collect();
+        let rows: Vec<Vec<String>> = std::iter::once(header)
+            .chain(self.data.iter().map(|line| {
+                self.columns
+                    .iter()
+                    .map(|column| match column {
+                        Column::State => line.state.to_uppercase(),
+                        column => line.cell(*column),
+                    })
+                    .collect()
+            }))
+            .collect();
+
+        let mut widths = vec![0; self.columns.len()];
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = max(*width, cell.len() + PADDING);
+            }
+        }
+
+        for row in &rows {
+            for (width, cell) in widths.iter().zip(row) {
+                write!(f, "{:width$}", cell, width = width)?;
+            }
+            writeln!(f)?;
         }
         Ok(())
     }
 }
 
+#[derive(Serialize)]
 pub struct Container<'a> {
-    id: &'a str,
+    // Not set for containers that are configured but not created
+    id: Option<&'a str>,
     name: &'a str,
     image: &'a str,
     state: &'a str,
     status: &'a str,
+    ports: Vec<String>,
+    // Configuration file the container was defined in, if any
+    source: Option<&'a str>,
+
+    // The following are only filled in for created containers, some of them
+    // only when their column is requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outdated: Option<Vec<&'static str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u64>,
 }
 
 impl<'a> Container<'a> {
     pub fn new(
-        id: &'a str,
+        id: Option<&'a str>,
         name: &'a str,
         image: &'a str,
         state: &'a str,
         status: &'a str,
+        ports: Vec<String>,
+        source: Option<&'a str>,
     ) -> Self {
         Container {
             id,
@@ -87,6 +143,286 @@ impl<'a> Container<'a> {
             image,
             state,
             status,
+            ports,
+            source,
+            created: None,
+            started: None,
+            stale: None,
+            outdated: None,
+            cpu: None,
+            memory: None,
+        }
+    }
+
+    fn cell(&self, column: Column) -> String {
+        match column {
+            Column::Id => match self.id {
+                Some(id) => id[..id.len().min(12)].to_string(),
+                None => EMPTY_ID.to_string(),
+            },
+            Column::Name => self.name.to_string(),
+            Column::Image => self.image.to_string(),
+            Column::State => self.state.to_string(),
+            Column::Status => self.status.to_string(),
+            Column::Ports => self.ports.join(", "),
+            Column::Created => self
+                .created
+                .map(|c| c.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+            Column::Uptime => self
+                .started
+                .map(|s| format_uptime(Utc::now().signed_duration_since(s)))
+                .unwrap_or_default(),
+            Column::Stale => match self.stale {
+                Some(true) => "yes".to_string(),
+                Some(false) => "no".to_string(),
+                None => String::new(),
+            },
+            Column::Outdated => match &self.outdated {
+                Some(changes) if changes.is_empty() => "no".to_string(),
+                Some(changes) => changes.join(", "),
+                None => String::new(),
+            },
+            Column::Cpu => self
+                .cpu
+                .map(|cpu| format!("{:.2}%", cpu))
+                .unwrap_or_default(),
+            Column::Memory => self.memory.map(format_bytes).unwrap_or_default(),
+        }
+    }
+
+    // Fills the `{{.Field}}` placeholders of a template, much like docker's
+    // --format does. Fields are named after the columns, plus .Source.
+    fn render(&self, template: &str) -> Result<String, String> {
+        let mut result = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            result.push_str(&rest[..start]);
+
+            let (field, tail) = match rest[start + 2..].split_once("}}") {
+                Some(split) => split,
+                None => return Err(format!("Unterminated placeholder in '{}'", template)),
+            };
+
+            let value = match field.trim().strip_prefix('.') {
+                Some("Source") => self.source.unwrap_or_default().to_string(),
+                Some(name) => match Column::from_str(name, true) {
+                    Ok(column) => self.cell(column),
+                    Err(_) => return Err(format!("Unknown field '{}' in template", field.trim())),
+                },
+                None => return Err(format!("Unknown field '{}' in template", field.trim())),
+            };
+            result.push_str(&value);
+
+            rest = tail;
+        }
+
+        result.push_str(rest);
+
+        Ok(result)
+    }
+}
+
+// Renders published ports the same way docker does, i.e. 0.0.0.0:8080->80/tcp
+pub fn format_ports(ports: &[Port]) -> Vec<String> {
+    let mut formatted: Vec<String> = vec![];
+
+    for port in ports {
+        let port = match (&port.ip, port.public_port) {
+            (Some(ip), Some(public)) => {
+                format!("{}:{}->{}/{}", ip, public, port.private_port, port.typ)
+            }
+            _ => format!("{}/{}", port.private_port, port.typ),
+        };
+
+        if !formatted.contains(&port) {
+            formatted.push(port);
         }
     }
+
+    formatted
+}
+
+// Uses the two largest units, i.e. 3h 25m
+fn format_uptime(uptime: chrono::Duration) -> String {
+    let seconds = uptime.num_seconds().max(0);
+    let units = [
+        ("d", seconds / 86400),
+        ("h", seconds / 3600 % 24),
+        ("m", seconds / 60 % 60),
+        ("s", seconds % 60),
+    ];
+
+    let first = units.iter().position(|(_, n)| *n != 0).unwrap_or(3);
+    units[first..]
+        .iter()
+        .take(2)
+        .filter(|(_, n)| *n != 0 || first == 3)
+        .map(|(unit, n)| format!("{}{}", n, unit))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit = "B";
+    for u in UNITS {
+        if size < 1024.0 {
+            break;
+        }
+        size /= 1024.0;
+        unit = u;
+    }
+
+    match unit {
+        "B" => format!("{}B", bytes),
+        unit => format!("{:.1}{}", size, unit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    const COLUMNS: [Column; 6] = [
+        Column::Id,
+        Column::Name,
+        Column::Image,
+        Column::State,
+        Column::Status,
+        Column::Ports,
+    ];
+
+    fn containers(columns: &[Column]) -> Containers<'static> {
+        let mut containers = Containers::new(columns);
+        let mut app = Container::new(
+            Some("0123456789abcdef"),
+            "app",
+            "quay.io/org/app:tag",
+            "running",
+            "Up 2 minutes",
+            vec!["0.0.0.0:8080->80/tcp".to_string(), "443/tcp".to_string()],
+            Some("/home/user/.denver.yml"),
+        );
+        app.created = Some(Utc.timestamp_opt(1666000000, 0).unwrap());
+        containers.push(app);
+        containers.push(Container::new(
+            None,
+            "db",
+            "quay.io/org/db:tag",
+            "not created",
+            "",
+            vec![],
+            Some("/home/user/.denver.yml"),
+        ));
+        containers
+    }
+
+    #[test]
+    fn test_render() {
+        let containers = containers(&COLUMNS);
+
+        let table = containers.render(&StatusFormat::Table).unwrap();
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("CONTAINER ID  NAME"));
+        assert!(lines[1].starts_with("0123456789ab  app"));
+        assert!(lines[1].contains("0.0.0.0:8080->80/tcp, 443/tcp"));
+        assert!(lines[2].starts_with("------------  db"));
+        assert!(lines[2].contains("NOT CREATED"));
+
+        let template = StatusFormat::Template("{{.Name}} {{ .State }} [{{.Ports}}]".to_string());
+        assert_eq!(
+            containers.render(&template).unwrap(),
+            "app running [0.0.0.0:8080->80/tcp, 443/tcp]\ndb not created []\n"
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&containers.render(&StatusFormat::Json).unwrap()).unwrap();
+        assert_eq!(
+            json[0],
+            serde_json::json!({
+                "id": "0123456789abcdef",
+                "name": "app",
+                "image": "quay.io/org/app:tag",
+                "state": "running",
+                "status": "Up 2 minutes",
+                "ports": ["0.0.0.0:8080->80/tcp", "443/tcp"],
+                "source": "/home/user/.denver.yml",
+                "created": "2022-10-17T09:46:40Z",
+            })
+        );
+        assert_eq!(json[1]["id"], serde_json::Value::Null);
+
+        let yaml: serde_json::Value =
+            serde_yaml::from_str(&containers.render(&StatusFormat::Yaml).unwrap()).unwrap();
+        assert_eq!(yaml, json);
+    }
+
+    #[test]
+    fn test_render_columns() {
+        let mut containers =
+            containers(&[Column::Name, Column::Stale, Column::Cpu, Column::Memory]);
+        containers.data[0].stale = Some(true);
+        containers.data[0].cpu = Some(12.345);
+        containers.data[0].memory = Some(150 * 1024 * 1024);
+
+        assert_eq!(
+            containers.render(&StatusFormat::Table).unwrap(),
+            concat!(
+                "NAME  STALE  CPU %   MEMORY    \n",
+                "app   yes    12.35%  150.0MiB  \n",
+                "db                             \n",
+            )
+        );
+
+        let template = StatusFormat::Template("{{.Name}}: {{.CPU}} {{.Created}}".to_string());
+        assert_eq!(
+            containers.render(&template).unwrap(),
+            "app: 12.35% 2022-10-17 09:46:40\ndb:  \n"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        let containers = containers(&COLUMNS);
+
+        assert!(containers
+            .render(&StatusFormat::Template("{{.Name".to_string()))
+            .is_err());
+        assert!(containers
+            .render(&StatusFormat::Template("{{.Names}}".to_string()))
+            .is_err());
+        assert!(containers
+            .render(&StatusFormat::Template("{{Name}}".to_string()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_format() {
+        for (seconds, expected) in [
+            (0, "0s"),
+            (42, "42s"),
+            (125, "2m 5s"),
+            (3600, "1h"),
+            (3 * 3600 + 25 * 60 + 10, "3h 25m"),
+            (2 * 86400 + 30, "2d"),
+            (86400 + 3600, "1d 1h"),
+        ] {
+            assert_eq!(
+                format_uptime(chrono::Duration::seconds(seconds)),
+                expected,
+                "{}",
+                seconds
+            );
+        }
+
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0GiB");
+    }
 }
@@ -0,0 +1,65 @@
+use std::io::{self, Write};
+
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+
+pub fn is_tty() -> bool {
+    io::stdin().is_tty()
+}
+
+// A yes/no question for the user, passed along so tests can answer it
+pub type Confirm = fn(&str) -> io::Result<bool>;
+
+// None when there is no terminal to ask on, reading would just hit EOF
+pub fn prompt() -> Option<Confirm> {
+    if is_tty() {
+        Some(confirm)
+    } else {
+        None
+    }
+}
+
+// Asks a yes/no question on stdin, anything but a yes is taken as a no
+pub fn confirm(question: &str) -> io::Result<bool> {
+    print!("{} [y/N] ", question);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+// Keeps the local terminal in raw mode while alive, so every keystroke is
+// forwarded to the container as is.
+pub struct RawMode {
+    enabled: bool,
+}
+
+impl RawMode {
+    pub fn enable(tty: bool) -> io::Result<Self> {
+        if tty {
+            terminal::enable_raw_mode()?;
+        }
+
+        Ok(RawMode { enabled: tty })
+    }
+
+    // Mirrors a non-zero exit code from the container. process::exit skips
+    // destructors, so the terminal is restored before leaving.
+    pub fn exit_on_failure(self, exit_code: u64) {
+        drop(self);
+
+        if exit_code != 0 {
+            std::process::exit(exit_code as i32);
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if self.enabled {
+            let _ = terminal::disable_raw_mode();
+        }
+    }
+}
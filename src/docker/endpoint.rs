@@ -0,0 +1,349 @@
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use shiplift::Docker;
+
+use crate::config::Config;
+
+const DEFAULT_SOCKET: &str = "/var/run/docker.sock";
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Tls {
+    // Directory holding ca.pem, cert.pem and key.pem
+    pub cert_path: String,
+    pub verify: bool,
+}
+
+// Where the docker daemon is listening
+#[derive(Debug, Eq, PartialEq)]
+pub enum Endpoint {
+    Unix(String),
+    Tcp {
+        host: String,
+        port: u16,
+        tls: Option<Tls>,
+    },
+}
+
+impl Endpoint {
+    // Parses a docker host URL, plain paths are taken as unix sockets
+    pub fn parse(url: &str, tls: Option<Tls>) -> Result<Self, String> {
+        let (scheme, address) = match url.split_once("://") {
+            Some(split) => split,
+            None if url.starts_with('/') => ("unix", url),
+            None => return Err(format!("Invalid docker host {}", url)),
+        };
+
+        match scheme {
+            "unix" => Ok(Endpoint::Unix(address.to_string())),
+            "tcp" | "https" => {
+                if scheme == "https" && tls.is_none() {
+                    return Err(format!(
+                        "{} needs TLS certificates, set tls.cert_path or DOCKER_CERT_PATH",
+                        url
+                    ));
+                }
+
+                let address = address.trim_end_matches('/');
+
+                // Accounts for bracketed IPv6 addresses
+                let (host, port) = match address.rsplit_once(':') {
+                    Some((host, port)) if !port.ends_with(']') => {
+                        let port = port
+                            .parse()
+                            .map_err(|_| format!("Invalid port '{}' in {}", port, url))?;
+                        (host, port)
+                    }
+                    _ if tls.is_some() => (address, 2376),
+                    _ => (address, 2375),
+                };
+
+                if host.is_empty() {
+                    return Err(format!("Missing host in {}", url));
+                }
+
+                Ok(Endpoint::Tcp {
+                    host: host.to_string(),
+                    port,
+                    tls,
+                })
+            }
+            scheme => Err(format!(
+                "Unsupported scheme '{}' in {}, expected unix, tcp or https",
+                scheme, url
+            )),
+        }
+    }
+
+    // Picks the endpoint set in the configuration, falling back to
+    // DOCKER_HOST, the current docker context and the default socket, in that
+    // order.
+    pub fn resolve(config: &Config) -> Result<Self, String> {
+        if let Some(socket) = &config.socket {
+            let tls = config.tls.as_ref().map(|tls| Tls {
+                cert_path: tls.cert_path.clone().unwrap_or_else(default_cert_path),
+                verify: tls.verify.unwrap_or(true),
+            });
+
+            return Endpoint::parse(socket, tls);
+        }
+
+        match env::var("DOCKER_HOST") {
+            Ok(host) if !host.is_empty() => return Endpoint::parse(&host, env_tls()),
+            _ => {}
+        }
+
+        if let Some(context) = current_context() {
+            if let Some(endpoint) = context_endpoint(&docker_config_dir(), &context)? {
+                return Ok(endpoint);
+            }
+        }
+
+        Ok(Endpoint::Unix(DEFAULT_SOCKET.to_string()))
+    }
+
+    pub fn connect(&self) -> Result<Docker, String> {
+        match self {
+            Endpoint::Unix(path) => Ok(Docker::unix(path)),
+            Endpoint::Tcp { host, port, .. } => {
+                let uri = format!("tcp://{}:{}", host, port)
+                    .parse()
+                    .map_err(|e| format!("Invalid docker host {}: {}", self, e))?;
+
+                Ok(Docker::host(uri))
+            }
+        }
+    }
+
+    // shiplift only takes TLS settings from DOCKER_CERT_PATH and
+    // DOCKER_TLS_VERIFY, these are the values they need for this endpoint.
+    // Without DOCKER_TLS_VERIFY, shiplift checks the daemon against the
+    // system's CAs instead of ca.pem.
+    fn tls_env(&self) -> [(&'static str, Option<String>); 2] {
+        match self {
+            Endpoint::Tcp { tls: Some(tls), .. } => [
+                ("DOCKER_CERT_PATH", Some(tls.cert_path.clone())),
+                ("DOCKER_TLS_VERIFY", tls.verify.then(|| "1".to_string())),
+            ],
+            _ => [("DOCKER_CERT_PATH", None), ("DOCKER_TLS_VERIFY", None)],
+        }
+    }
+
+    // Changing the environment isn't thread safe, this has to be done before
+    // the runtime starts.
+    pub fn export_tls(&self) {
+        for (name, value) in self.tls_env() {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Endpoint::Unix(path) => write!(f, "unix://{}", path),
+            Endpoint::Tcp {
+                host,
+                port,
+                tls: Some(_),
+            } => write!(f, "https://{}:{}", host, port),
+            Endpoint::Tcp { host, port, .. } => write!(f, "tcp://{}:{}", host, port),
+        }
+    }
+}
+
+fn docker_config_dir() -> PathBuf {
+    match env::var("DOCKER_CONFIG") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(&env::var("HOME").unwrap_or_default()).join(".docker"),
+    }
+}
+
+fn default_cert_path() -> String {
+    docker_config_dir().to_string_lossy().to_string()
+}
+
+// TLS settings as understood by the docker CLI
+fn env_tls() -> Option<Tls> {
+    let verify = env::var("DOCKER_TLS_VERIFY").map_or(false, |v| !v.is_empty());
+    let cert_path = env::var("DOCKER_CERT_PATH").ok();
+
+    if !verify && cert_path.is_none() {
+        return None;
+    }
+
+    Some(Tls {
+        cert_path: cert_path.unwrap_or_else(default_cert_path),
+        verify,
+    })
+}
+
+// The context selected with DOCKER_CONTEXT or `docker context use`
+fn current_context() -> Option<String> {
+    if let Ok(context) = env::var("DOCKER_CONTEXT") {
+        return Some(context);
+    }
+
+    let config = std::fs::read_to_string(docker_config_dir().join("config.json")).ok()?;
+    let config: Value = serde_json::from_str(&config).ok()?;
+
+    config["currentContext"].as_str().map(|c| c.to_string())
+}
+
+// Looks the endpoint of a context up in the docker CLI's context store, where
+// contexts are kept under the SHA256 of their name.
+fn context_endpoint(docker_dir: &Path, name: &str) -> Result<Option<Endpoint>, String> {
+    if name.is_empty() || name == "default" {
+        return Ok(None);
+    }
+
+    let hash = format!("{:x}", Sha256::digest(name.as_bytes()));
+    let meta = docker_dir
+        .join("contexts/meta")
+        .join(&hash)
+        .join("meta.json");
+
+    let meta = std::fs::read_to_string(&meta)
+        .map_err(|e| format!("Failed to read docker context {}: {}", name, e))?;
+    let meta: Value = serde_json::from_str(&meta)
+        .map_err(|e| format!("Failed to parse docker context {}: {}", name, e))?;
+
+    let endpoint = &meta["Endpoints"]["docker"];
+    let host = match endpoint["Host"].as_str() {
+        Some(host) => host,
+        None => return Err(format!("Docker context {} has no docker endpoint", name)),
+    };
+
+    let tls_dir = docker_dir.join("contexts/tls").join(&hash).join("docker");
+    let tls = tls_dir.is_dir().then(|| Tls {
+        cert_path: tls_dir.to_string_lossy().to_string(),
+        verify: !endpoint["SkipTLSVerify"].as_bool().unwrap_or(false),
+    });
+
+    Endpoint::parse(host, tls).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tls() -> Option<Tls> {
+        Some(Tls {
+            cert_path: "/certs".to_string(),
+            verify: true,
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            Endpoint::parse("/var/run/docker.sock", None),
+            Ok(Endpoint::Unix("/var/run/docker.sock".to_string()))
+        );
+        assert_eq!(
+            Endpoint::parse("unix:///run/podman/podman.sock", None),
+            Ok(Endpoint::Unix("/run/podman/podman.sock".to_string()))
+        );
+        assert_eq!(
+            Endpoint::parse("tcp://10.0.0.2", None),
+            Ok(Endpoint::Tcp {
+                host: "10.0.0.2".to_string(),
+                port: 2375,
+                tls: None
+            })
+        );
+        assert_eq!(
+            Endpoint::parse("https://[::1]", tls()).unwrap().to_string(),
+            "https://[::1]:2376"
+        );
+        assert_eq!(
+            Endpoint::parse("tcp://docker.local:4243/", tls())
+                .unwrap()
+                .to_string(),
+            "https://docker.local:4243"
+        );
+
+        assert!(Endpoint::parse("https://docker.local", None).is_err());
+        assert!(Endpoint::parse("ssh://docker.local", None).is_err());
+        assert!(Endpoint::parse("tcp://docker.local:port", None).is_err());
+        assert!(Endpoint::parse("docker.sock", None).is_err());
+    }
+
+    #[test]
+    fn test_tls_env() {
+        let plain = Endpoint::parse("tcp://10.0.0.2", None).unwrap();
+        assert_eq!(
+            plain.tls_env(),
+            [("DOCKER_CERT_PATH", None), ("DOCKER_TLS_VERIFY", None)]
+        );
+
+        let secure = Endpoint::parse("https://10.0.0.2", tls()).unwrap();
+        assert_eq!(
+            secure.tls_env(),
+            [
+                ("DOCKER_CERT_PATH", Some("/certs".to_string())),
+                ("DOCKER_TLS_VERIFY", Some("1".to_string()))
+            ]
+        );
+
+        let unverified = Tls {
+            verify: false,
+            ..tls().unwrap()
+        };
+        let secure = Endpoint::parse("https://10.0.0.2", Some(unverified)).unwrap();
+        assert_eq!(
+            secure.tls_env(),
+            [
+                ("DOCKER_CERT_PATH", Some("/certs".to_string())),
+                ("DOCKER_TLS_VERIFY", None)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_context_endpoint() {
+        let dir = env::temp_dir().join(format!("denver-context-{}", std::process::id()));
+        let hash = format!("{:x}", Sha256::digest(b"remote"));
+        let meta = dir.join("contexts/meta").join(&hash);
+        std::fs::create_dir_all(&meta).unwrap();
+        std::fs::write(
+            meta.join("meta.json"),
+            r#"{"Name":"remote","Endpoints":{"docker":{"Host":"tcp://10.0.0.2:2376","SkipTLSVerify":true}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(context_endpoint(&dir, "default"), Ok(None));
+        assert_eq!(
+            context_endpoint(&dir, "remote"),
+            Ok(Some(Endpoint::Tcp {
+                host: "10.0.0.2".to_string(),
+                port: 2376,
+                tls: None
+            }))
+        );
+
+        let tls_dir = dir.join("contexts/tls").join(&hash).join("docker");
+        std::fs::create_dir_all(&tls_dir).unwrap();
+        assert_eq!(
+            context_endpoint(&dir, "remote"),
+            Ok(Some(Endpoint::Tcp {
+                host: "10.0.0.2".to_string(),
+                port: 2376,
+                tls: Some(Tls {
+                    cert_path: tls_dir.to_string_lossy().to_string(),
+                    verify: false
+                })
+            }))
+        );
+
+        assert!(context_endpoint(&dir, "missing").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
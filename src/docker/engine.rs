@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use shiplift::rep::Container;
+
+use crate::cli::Common;
+use crate::config::{ContainerConfig, NetworkConfig};
+
+use super::{Details, DockerError, Health, Usage};
+
+// Operations denver needs from a container engine to manage its containers
+#[async_trait(?Send)]
+pub trait ContainerEngine {
+    async fn build_image(
+        &self,
+        args: &Common,
+        container: &ContainerConfig,
+    ) -> Result<(), DockerError>;
+
+    // Returns the ID of the new container
+    async fn create_container(
+        &self,
+        name: &str,
+        container: &ContainerConfig,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<String, DockerError>;
+
+    async fn run_container(&self, id: &String) -> Result<(), DockerError>;
+
+    // Kills the container if it doesn't stop within the timeout
+    async fn stop_container(&self, id: &String, timeout: Duration) -> Result<(), DockerError>;
+
+    async fn remove_container(
+        &self,
+        id: &String,
+        force: bool,
+        volumes: bool,
+    ) -> Result<(), DockerError>;
+
+    // Starts the container with the terminal attached to it, returning its
+    // exit code once it stops.
+    async fn attach_container(
+        &self,
+        id: &str,
+        tty: bool,
+        auto_remove: bool,
+    ) -> Result<u64, DockerError>;
+
+    async fn health(&self, id: &str) -> Result<Health, DockerError>;
+
+    async fn inspect_container(&self, id: &str) -> Result<Details, DockerError>;
+
+    // ID of the image with the given tag, if there is one
+    async fn image_id(&self, tag: &str) -> Result<Option<String>, DockerError>;
+
+    async fn usage(&self, id: &str) -> Result<Usage, DockerError>;
+
+    async fn connect_networks(
+        &self,
+        id: &str,
+        container: &ContainerConfig,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<(), DockerError>;
+
+    async fn remove_image(&self, tag: &str) -> Result<(), DockerError>;
+
+    // Names of the networks created by denver
+    async fn managed_networks(&self) -> Result<Vec<String>, DockerError>;
+
+    async fn remove_network(&self, name: &str) -> Result<(), DockerError>;
+
+    async fn list_volumes(&self) -> Result<Vec<String>, DockerError>;
+
+    async fn remove_volume(&self, name: &str) -> Result<(), DockerError>;
+
+    // Containers managed by denver, stopped ones are only listed with all
+    async fn list_containers(&self, all: bool) -> Result<Vec<Container>, DockerError>;
+}
@@ -0,0 +1,883 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::ops::Deref;
+use std::thread;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crossterm::style::Stylize;
+use crossterm::terminal;
+use crossterm::tty::IsTty;
+use futures::{AsyncWriteExt, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+use shiplift::rep::NetworkDetails;
+use shiplift::tty::{Multiplexer, TtyChunk};
+use shiplift::RmContainerOptions;
+use shiplift::{
+    rep::Container, BuildOptions, ContainerConnectionOptions, ContainerFilter,
+    ContainerListOptions, ContainerOptions, Docker, Exec, ExecContainerOptions, ExecResizeOptions,
+    LogsOptions, NetworkCreateOptions, NetworkListOptions,
+};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::mpsc;
+
+use crate::cli::{Common, Logs};
+use crate::config::{resolve_env, Config, ContainerConfig, Engine, NetworkConfig};
+
+use self::endpoint::Endpoint;
+pub use self::engine::ContainerEngine;
+
+mod endpoint;
+mod engine;
+
+const DENVER_LABEL: (&str, &str) = ("manager", "denver");
+// Hash of the configuration and image a container was created from
+pub const HASH_LABEL: &str = "denver.config-hash";
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+pub enum DockerError {
+    Connect(String),
+    Build(String),
+    Run(String),
+    List(String),
+    Stop(String),
+    Remove(String),
+    Exec(String),
+    Logs(String),
+    Network(String),
+    Inspect(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Health {
+    Starting,
+    Healthy,
+    Unhealthy,
+    // Stopped, with its exit code
+    Exited(u64),
+    // Without a healthcheck
+    Unknown,
+}
+
+// Health status as reported when inspecting a container
+impl From<&str> for Health {
+    fn from(status: &str) -> Self {
+        match status {
+            "healthy" => Health::Healthy,
+            "unhealthy" => Health::Unhealthy,
+            "starting" => Health::Starting,
+            _ => Health::Unknown,
+        }
+    }
+}
+
+// What denver needs to know from inspecting a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct Details {
+    pub created: DateTime<Utc>,
+    pub started: DateTime<Utc>,
+    pub image_id: String,
+}
+
+// Resources used by a running container, CPU is a percentage of a single core
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Usage {
+    pub cpu: f64,
+    pub memory: u64,
+}
+
+// The parts of a stats sample used to compute usage, read back from
+// shiplift's own type.
+#[derive(Deserialize)]
+struct Stats {
+    cpu_stats: CpuStats,
+    precpu_stats: CpuStats,
+    memory_stats: MemoryStats,
+}
+
+#[derive(Deserialize)]
+struct CpuStats {
+    cpu_usage: CpuUsage,
+    #[serde(default)]
+    system_cpu_usage: u64,
+    online_cpus: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CpuUsage {
+    total_usage: u64,
+    percpu_usage: Option<Vec<u64>>,
+}
+
+#[derive(Deserialize)]
+struct MemoryStats {
+    #[serde(default)]
+    usage: u64,
+    #[serde(default)]
+    stats: HashMap<String, u64>,
+}
+
+impl Stats {
+    // Computed the same way docker stats does
+    fn usage(&self) -> Usage {
+        let (before, after) = (&self.precpu_stats, &self.cpu_stats);
+        let used = after
+            .cpu_usage
+            .total_usage
+            .saturating_sub(before.cpu_usage.total_usage);
+        let total = after
+            .system_cpu_usage
+            .saturating_sub(before.system_cpu_usage);
+        let cpus = match after.online_cpus {
+            Some(cpus) if cpus > 0 => cpus,
+            _ => after
+                .cpu_usage
+                .percpu_usage
+                .as_ref()
+                .map_or(1, |p| p.len().max(1) as u64),
+        };
+
+        let cpu = match total {
+            0 => 0.0,
+            total => used as f64 / total as f64 * cpus as f64 * 100.0,
+        };
+
+        // The page cache is left out, as named by cgroup v1 and v2
+        let stats = &self.memory_stats.stats;
+        let cache = stats
+            .get("total_inactive_file")
+            .or_else(|| stats.get("inactive_file"))
+            .copied()
+            .unwrap_or_default();
+
+        Usage {
+            cpu,
+            memory: self.memory_stats.usage.saturating_sub(cache),
+        }
+    }
+}
+
+pub struct DockerClient {
+    docker: Docker,
+    endpoint: Endpoint,
+    engine: Engine,
+}
+
+impl DockerClient {
+    pub fn new(config: &Config) -> Result<Self, DockerError> {
+        let endpoint = Endpoint::resolve(config).map_err(DockerError::Connect)?;
+        let docker = endpoint.connect().map_err(DockerError::Connect)?;
+
+        Ok(DockerClient {
+            docker,
+            endpoint,
+            engine: config.engine,
+        })
+    }
+
+    // Sets up the environment shiplift reads TLS settings from. It must be
+    // called before any thread is started, errors are left for new to report.
+    pub fn export_tls(config: &Config) {
+        if let Ok(endpoint) = Endpoint::resolve(config) {
+            endpoint.export_tls();
+        }
+    }
+
+    // Checks the daemon is reachable, so connection problems are reported
+    // up front rather than on whatever call happens to come first.
+    pub async fn ping(&self) -> Result<(), DockerError> {
+        match self.docker.ping().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Connect(format!(
+                "Cannot connect to the docker daemon at {}: {}",
+                self.endpoint, e
+            ))),
+        }
+    }
+
+    // Podman reports image IDs both with and without the sha256: prefix,
+    // they are compared without it.
+    fn image_digest(id: &str) -> String {
+        id.trim_start_matches("sha256:").to_string()
+    }
+
+    fn create_run_options(
+        &self,
+        name: &str,
+        container: &ContainerConfig,
+        hash: &str,
+    ) -> Result<ContainerOptions, DockerError> {
+        static EMPTY_VEC: Vec<String> = vec![];
+        let run_options = &container.run;
+        let workspace_volume = format!("{}:{}", run_options.workspace, run_options.workspace);
+        let mut volumes: Vec<&str> = vec![&workspace_volume[..]];
+        let configured_volumes = run_options.volumes.as_ref().unwrap_or(&EMPTY_VEC);
+
+        volumes.append(
+            &mut configured_volumes
+                .iter()
+                .map(|s| s.deref())
+                .collect::<Vec<&str>>(),
+        );
+
+        let env = resolve_env(run_options).map_err(DockerError::Run)?;
+        let args = &run_options.args;
+        let mut options = ContainerOptions::builder(&container.tag);
+
+        // Podman removes auto-remove containers before a wait on them
+        // returns, losing their exit code. Containers we might attach to are
+        // removed once they exit in attach_container instead.
+        let auto_remove = match self.engine {
+            Engine::Docker => args.auto_remove,
+            Engine::Podman => args.auto_remove && !args.interactive,
+        };
+
+        options
+            .name(name)
+            .attach_stdin(args.interactive)
+            .attach_stdout(args.interactive)
+            .attach_stderr(args.interactive)
+            .tty(args.tty)
+            .auto_remove(auto_remove)
+            .privileged(args.privileged)
+            .init(args.init)
+            .read_only(args.read_only)
+            .volumes(volumes)
+            .working_dir(&run_options.workspace)
+            .env(&env)
+            .labels(&HashMap::from([DENVER_LABEL, (HASH_LABEL, hash)]));
+
+        if let Some(cap_add) = &args.cap_add {
+            options.capabilities(cap_add.iter().map(|s| s.deref()).collect());
+        }
+
+        if let Some(cap_drop) = &args.cap_drop {
+            options.cap_drop(cap_drop.iter().map(|s| s.deref()).collect());
+        }
+
+        if let Some(security_opt) = &args.security_opt {
+            options.security_opt(security_opt.iter().map(|s| s.deref()).collect());
+        }
+
+        if let Some(devices) = &args.devices {
+            options.devices(devices.iter().map(|d| Self::device_mapping(d)).collect());
+        }
+
+        if let Some(user) = &args.user {
+            options.user(user);
+        }
+
+        if let Some(hostname) = &args.hostname {
+            options.hostname(hostname);
+        }
+
+        if let Some(shm_size) = args.shm_size {
+            options.shm_size(shm_size);
+        }
+
+        for (name, limit) in args.ulimits.iter().flatten() {
+            options.ulimit(name, limit.soft(), limit.hard());
+        }
+
+        if let Some(entrypoint) = &run_options.entrypoint {
+            options.entrypoint(entrypoint);
+        }
+
+        for mapping in run_options.ports.iter().flatten() {
+            for (host, container) in mapping.pairs() {
+                let protocol = &mapping.protocol;
+
+                if let Some(ip) = &mapping.host_ip {
+                    options.expose_ip(ip, container.into(), protocol, host.into());
+                } else {
+                    options.expose(container.into(), protocol, host.into());
+                }
+            }
+        }
+
+        if let Some(signal) = &run_options.stop_signal {
+            options.stop_signal(signal);
+        }
+
+        // Also honoured by a plain docker stop or the daemon shutting down
+        if let Some(timeout) = run_options.stop_timeout {
+            options.stop_timeout(timeout);
+        }
+
+        if let Some(healthcheck) = &run_options.healthcheck {
+            options.healthcheck(
+                healthcheck.test(),
+                healthcheck.interval,
+                healthcheck.timeout,
+                healthcheck.retries,
+                healthcheck.start_period,
+            );
+        }
+
+        Ok(options.build())
+    }
+
+    // Translates a `host[:container[:permissions]]` device into the format
+    // expected by the docker API
+    fn device_mapping(device: &str) -> HashMap<String, String> {
+        let mut parts = device.splitn(3, ':');
+        let host = parts.next().unwrap_or_default();
+        let container = parts.next().unwrap_or(host);
+        let permissions = parts.next().unwrap_or("rwm");
+
+        HashMap::from([
+            ("PathOnHost".to_string(), host.to_string()),
+            ("PathInContainer".to_string(), container.to_string()),
+            ("CgroupPermissions".to_string(), permissions.to_string()),
+        ])
+    }
+
+    pub async fn exec(
+        &self,
+        id: &str,
+        command: &[String],
+        working_dir: &str,
+        tty: bool,
+    ) -> Result<u64, DockerError> {
+        let options = ExecContainerOptions::builder()
+            .cmd(command.iter().map(|s| s.deref()).collect())
+            .working_dir(working_dir)
+            .attach_stdin(true)
+            .attach_stdout(true)
+            .attach_stderr(true)
+            .tty(tty)
+            .build();
+
+        let exec = match Exec::create(&self.docker, id, &options).await {
+            Ok(exec) => exec,
+            Err(e) => return Err(DockerError::Exec(e.to_string())),
+        };
+
+        let multiplexer = match exec.start().await {
+            Ok(multiplexer) => multiplexer,
+            Err(e) => return Err(DockerError::Exec(e.to_string())),
+        };
+
+        let resize = |width, height| {
+            let exec = &exec;
+
+            async move {
+                let options = ExecResizeOptions::builder()
+                    .width(width)
+                    .height(height)
+                    .build();
+
+                exec.resize(&options).await
+            }
+        };
+
+        Self::pipe_tty(multiplexer, tty, resize)
+            .await
+            .map_err(|e| DockerError::Exec(e.to_string()))?;
+
+        match exec.inspect().await {
+            Ok(details) => Ok(details.exit_code.unwrap_or_default()),
+            Err(e) => Err(DockerError::Exec(e.to_string())),
+        }
+    }
+
+    async fn pipe_tty<F, Fut>(
+        multiplexer: Multiplexer<'_>,
+        tty: bool,
+        resize: F,
+    ) -> Result<(), shiplift::Error>
+    where
+        F: Fn(u64, u64) -> Fut,
+        Fut: Future<Output = Result<(), shiplift::Error>>,
+    {
+        let (reader, mut writer) = multiplexer.split();
+        let mut reader = Box::pin(reader);
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(32);
+
+        // Reading from stdin blocks, so we do it on a plain thread that won't
+        // keep the process alive once the remote end is done.
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut buf = [0; 1024];
+
+            loop {
+                match stdin.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => {
+                        if tx.blocking_send(buf[..n].to_vec()).is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+
+        let mut window_change = signal(SignalKind::window_change())?;
+        let mut stdin_open = true;
+
+        if tty {
+            if let Ok((width, height)) = terminal::size() {
+                resize(width.into(), height.into()).await?;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                chunk = reader.next() => match chunk {
+                    Some(Ok(TtyChunk::StdOut(bytes))) => {
+                        let mut stdout = io::stdout();
+                        stdout.write_all(&bytes)?;
+                        stdout.flush()?;
+                    }
+                    Some(Ok(TtyChunk::StdErr(bytes))) => {
+                        let mut stderr = io::stderr();
+                        stderr.write_all(&bytes)?;
+                        stderr.flush()?;
+                    }
+                    Some(Ok(TtyChunk::StdIn(_))) => {}
+                    Some(Err(e)) => return Err(e),
+                    None => break,
+                },
+                input = rx.recv(), if stdin_open => match input {
+                    Some(input) => writer.write_all(&input).await?,
+                    // Closing our end lets the container see the end of stdin
+                    None => {
+                        writer.close().await?;
+                        stdin_open = false;
+                    }
+                },
+                Some(_) = window_change.recv(), if tty => {
+                    if let Ok((width, height)) = terminal::size() {
+                        resize(width.into(), height.into()).await?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn logs(&self, id: &str, args: &Logs) -> Result<(), DockerError> {
+        let mut options = LogsOptions::builder();
+
+        options
+            .stdout(true)
+            .stderr(true)
+            .follow(args.follow)
+            .timestamps(args.timestamps);
+
+        if let Some(tail) = args.tail {
+            options.tail(&tail.to_string());
+        }
+
+        if let Some(since) = &args.since {
+            options.since(since);
+        }
+
+        let colored = io::stderr().is_tty();
+        let mut stream = self.docker.containers().get(id).logs(&options.build());
+
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(TtyChunk::StdOut(bytes)) => print!("{}", String::from_utf8_lossy(&bytes)),
+                Ok(TtyChunk::StdErr(bytes)) => {
+                    let log = String::from_utf8_lossy(&bytes);
+
+                    if colored {
+                        eprint!("{}", log.as_ref().red());
+                    } else {
+                        eprint!("{}", log);
+                    }
+                }
+                Ok(TtyChunk::StdIn(_)) => {}
+                Err(e) => return Err(DockerError::Logs(e.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_network(
+        &self,
+        name: &str,
+        network: Option<&NetworkConfig>,
+    ) -> Result<(), DockerError> {
+        let (label_key, label_value) = DENVER_LABEL;
+        let mut options = NetworkCreateOptions::builder(name);
+
+        options.label(HashMap::from([(
+            label_key.to_string(),
+            label_value.to_string(),
+        )]));
+
+        if let Some(driver) = network.and_then(|n| n.driver.as_ref()) {
+            options.driver(driver);
+        }
+
+        match self.docker.networks().create(&options.build()).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Network(e.to_string())),
+        }
+    }
+
+    async fn list_networks(&self) -> Result<Vec<NetworkDetails>, DockerError> {
+        let options = NetworkListOptions::default();
+
+        match self.docker.networks().list(&options).await {
+            Ok(networks) => Ok(networks),
+            Err(e) => Err(DockerError::Network(e.to_string())),
+        }
+    }
+
+    fn is_managed(network: &NetworkDetails) -> bool {
+        let (label_key, label_value) = DENVER_LABEL;
+
+        network
+            .labels
+            .as_ref()
+            .and_then(|l| l.get(label_key))
+            .map_or(false, |v| v == label_value)
+    }
+
+    // Removes networks created by denver that have no containers attached
+    pub async fn prune_networks(&self) -> Result<Vec<String>, DockerError> {
+        let mut removed = vec![];
+
+        for network in self.list_networks().await? {
+            if !Self::is_managed(&network) {
+                continue;
+            }
+
+            // Listing networks doesn't report attached containers
+            let network = self.docker.networks().get(&network.id);
+            let details = match network.inspect().await {
+                Ok(details) => details,
+                Err(e) => return Err(DockerError::Network(e.to_string())),
+            };
+
+            if !details.containers.is_empty() {
+                continue;
+            }
+
+            if let Err(e) = network.delete().await {
+                return Err(DockerError::Network(e.to_string()));
+            }
+
+            removed.push(details.name);
+        }
+
+        Ok(removed)
+    }
+}
+
+#[async_trait(?Send)]
+impl ContainerEngine for DockerClient {
+    async fn build_image(
+        &self,
+        args: &Common,
+        container: &ContainerConfig,
+    ) -> Result<(), DockerError> {
+        let docker = &self.docker;
+        let build_options = &container.build;
+
+        let mut options = BuildOptions::builder(&build_options.context);
+
+        options
+            .dockerfile(
+                build_options
+                    .dockerfile
+                    .as_ref()
+                    .unwrap_or(&"Dockerfile".to_string()),
+            )
+            .tag(&container.tag)
+            .nocache(args.no_cache);
+
+        if let Some(buildargs) = &build_options.build_args {
+            for (k, v) in buildargs {
+                options.buildargs(k, v);
+            }
+        }
+
+        let options = options.build();
+
+        let mut stream = docker.images().build(&options);
+        while let Some(build_result) = stream.next().await {
+            match build_result {
+                Ok(output) => {
+                    // Podman only sets `error`, docker adds `errorDetail`
+                    // with the same message.
+                    if let Value::String(error) = &output["error"] {
+                        return Err(DockerError::Build(error.trim_end().to_string()));
+                    }
+
+                    let stream = &output["stream"];
+                    match stream {
+                        Value::String(log) => print!("{}", log),
+                        Value::Null => {}
+                        _ => println!("{:?}", stream),
+                    }
+                }
+                Err(e) => match e {
+                    // Don't really care about SerdeJsonErrors for now
+                    shiplift::Error::SerdeJsonError(_) => {}
+                    e => return Err(DockerError::Build(format!("{:?}", e))),
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn create_container(
+        &self,
+        name: &str,
+        container: &ContainerConfig,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<String, DockerError> {
+        let docker = &self.docker;
+        let image_id = self.image_id(&container.tag).await?.unwrap_or_default();
+        let hash = container
+            .hash(&image_id, networks)
+            .map_err(DockerError::Run)?;
+        let options = self.create_run_options(name, container, &hash)?;
+
+        match docker.containers().create(&options).await {
+            Ok(info) => Ok(info.id),
+            Err(e) => Err(DockerError::Run(e.to_string())),
+        }
+    }
+
+    async fn run_container(&self, id: &String) -> Result<(), DockerError> {
+        let docker = &self.docker;
+
+        match docker.containers().get(id).start().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Run(e.to_string())),
+        }
+    }
+
+    async fn stop_container(&self, id: &String, timeout: Duration) -> Result<(), DockerError> {
+        let docker = &self.docker;
+
+        match docker.containers().get(id).stop(Some(timeout)).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Stop(e.to_string())),
+        }
+    }
+
+    async fn remove_container(
+        &self,
+        id: &String,
+        force: bool,
+        volumes: bool,
+    ) -> Result<(), DockerError> {
+        let docker = &self.docker;
+
+        let options = RmContainerOptions::builder()
+            .force(force)
+            .volumes(volumes)
+            .build();
+
+        match docker.containers().get(id).remove(options).await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Remove(e.to_string())),
+        }
+    }
+
+    async fn attach_container(
+        &self,
+        id: &str,
+        tty: bool,
+        auto_remove: bool,
+    ) -> Result<u64, DockerError> {
+        let container = self.docker.containers().get(id);
+
+        // Attach before starting so no output is lost
+        let multiplexer = match container.attach().await {
+            Ok(multiplexer) => multiplexer,
+            Err(e) => return Err(DockerError::Run(e.to_string())),
+        };
+
+        if let Err(e) = container.start().await {
+            return Err(DockerError::Run(e.to_string()));
+        }
+
+        let resize = |width, height| container.resize(height, width);
+
+        Self::pipe_tty(multiplexer, tty, resize)
+            .await
+            .map_err(|e| DockerError::Run(e.to_string()))?;
+
+        let status_code = match container.wait().await {
+            Ok(exit) => exit.status_code,
+            Err(e) => return Err(DockerError::Run(e.to_string())),
+        };
+
+        if self.engine == Engine::Podman && auto_remove {
+            self.remove_container(&id.to_string(), true, false).await?;
+        }
+
+        Ok(status_code)
+    }
+
+    async fn health(&self, id: &str) -> Result<Health, DockerError> {
+        let state = match self.docker.containers().get(id).inspect().await {
+            Ok(details) => details.state,
+            Err(e) => return Err(DockerError::Inspect(e.to_string())),
+        };
+
+        if !state.running {
+            return Ok(Health::Exited(state.exit_code));
+        }
+
+        Ok(state.health.map_or(Health::Unknown, |health| {
+            Health::from(health.status.as_str())
+        }))
+    }
+
+    async fn inspect_container(&self, id: &str) -> Result<Details, DockerError> {
+        match self.docker.containers().get(id).inspect().await {
+            Ok(details) => Ok(Details {
+                created: details.created,
+                started: details.state.started_at,
+                image_id: Self::image_digest(&details.image),
+            }),
+            Err(e) => Err(DockerError::Inspect(e.to_string())),
+        }
+    }
+
+    async fn image_id(&self, tag: &str) -> Result<Option<String>, DockerError> {
+        match self.docker.images().get(tag).inspect().await {
+            Ok(details) => Ok(Some(Self::image_digest(&details.id))),
+            Err(shiplift::Error::Fault { code, .. }) if code.as_u16() == 404 => Ok(None),
+            Err(e) => Err(DockerError::Inspect(e.to_string())),
+        }
+    }
+
+    async fn usage(&self, id: &str) -> Result<Usage, DockerError> {
+        // The first sample has nothing in precpu_stats, the second one is
+        // used when the daemon gets to send it
+        let samples: Vec<_> = self
+            .docker
+            .containers()
+            .get(id)
+            .stats()
+            .take(2)
+            .collect()
+            .await;
+
+        let stats = match samples.into_iter().last() {
+            Some(Ok(stats)) => stats,
+            Some(Err(e)) => return Err(DockerError::Inspect(e.to_string())),
+            None => return Err(DockerError::Inspect(format!("No stats for {}", id))),
+        };
+        let stats: Stats = serde_json::to_value(stats)
+            .and_then(serde_json::from_value)
+            .map_err(|e| DockerError::Inspect(e.to_string()))?;
+
+        Ok(stats.usage())
+    }
+
+    async fn remove_image(&self, tag: &str) -> Result<(), DockerError> {
+        match self.docker.images().get(tag).delete().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Remove(e.to_string())),
+        }
+    }
+
+    async fn managed_networks(&self) -> Result<Vec<String>, DockerError> {
+        Ok(self
+            .list_networks()
+            .await?
+            .into_iter()
+            .filter(Self::is_managed)
+            .map(|n| n.name)
+            .collect())
+    }
+
+    async fn remove_network(&self, name: &str) -> Result<(), DockerError> {
+        match self.docker.networks().get(name).delete().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Network(e.to_string())),
+        }
+    }
+
+    async fn list_volumes(&self) -> Result<Vec<String>, DockerError> {
+        match self.docker.volumes().list().await {
+            Ok(volumes) => Ok(volumes.into_iter().map(|v| v.name).collect()),
+            Err(e) => Err(DockerError::List(e.to_string())),
+        }
+    }
+
+    async fn remove_volume(&self, name: &str) -> Result<(), DockerError> {
+        match self.docker.volumes().get(name).delete().await {
+            Ok(_) => Ok(()),
+            Err(e) => Err(DockerError::Remove(e.to_string())),
+        }
+    }
+
+    async fn connect_networks(
+        &self,
+        id: &str,
+        container: &ContainerConfig,
+        networks: Option<&HashMap<String, NetworkConfig>>,
+    ) -> Result<(), DockerError> {
+        let attachments = match &container.run.network {
+            Some(attachments) => attachments,
+            None => return Ok(()),
+        };
+        let existing = self.list_networks().await?;
+
+        for attachment in attachments {
+            let name = attachment.name();
+
+            if !existing.iter().any(|n| n.name == name) {
+                println!("Creating network {}", name);
+                self.create_network(name, networks.and_then(|n| n.get(name)))
+                    .await?;
+            }
+
+            let options = ContainerConnectionOptions::builder(id)
+                .aliases(attachment.aliases())
+                .build();
+
+            if let Err(e) = self.docker.networks().get(name).connect(&options).await {
+                return Err(DockerError::Network(e.to_string()));
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_containers(&self, all: bool) -> Result<Vec<Container>, DockerError> {
+        let (label_key, label_value) = DENVER_LABEL;
+        let mut options = ContainerListOptions::builder();
+
+        if all {
+            options.all();
+        }
+
+        // Label filters are not reliably honoured by podman's docker
+        // compatible API, its containers get filtered below instead.
+        if self.engine == Engine::Docker {
+            options.filter(vec![ContainerFilter::Label(
+                label_key.to_string(),
+                label_value.to_string(),
+            )]);
+        }
+
+        let containers = match self.docker.containers().list(&options.build()).await {
+            Ok(info) => info,
+            Err(e) => return Err(DockerError::List(format!("{:?}", e))),
+        };
+
+        Ok(containers
+            .into_iter()
+            .filter(|c| c.labels.get(label_key).map(|v| v.as_str()) == Some(label_value))
+            .collect())
+    }
+}
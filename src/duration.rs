@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+// Parses human friendly durations like "90", "500ms", "30s" or "1h30m".
+// Bare numbers are taken as seconds.
+pub fn parse(duration: &str) -> Result<Duration, String> {
+    let duration = duration.trim();
+
+    if duration.is_empty() {
+        return Err("Empty duration".to_string());
+    }
+
+    if let Ok(secs) = duration.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = duration;
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return Err(format!("Invalid duration: {}", duration));
+        }
+
+        let value: u64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("Invalid duration: {}", duration))?;
+        rest = &rest[digits..];
+
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let part = match &rest[..unit] {
+            "ms" => Some(Duration::from_millis(value)),
+            "s" => Some(Duration::from_secs(value)),
+            "m" => value.checked_mul(60).map(Duration::from_secs),
+            "h" => value.checked_mul(60 * 60).map(Duration::from_secs),
+            "d" => value.checked_mul(24 * 60 * 60).map(Duration::from_secs),
+            u => return Err(format!("Unknown unit '{}' in duration: {}", u, duration)),
+        };
+        rest = &rest[unit..];
+
+        total = part
+            .and_then(|part| total.checked_add(part))
+            .ok_or_else(|| format!("Duration too long: {}", duration))?;
+    }
+
+    Ok(total)
+}
+
+// Docker takes stop timeouts in whole seconds, anything shorter would kill
+// the container right away.
+pub fn parse_secs(duration: &str) -> Result<Duration, String> {
+    let parsed = parse(duration)?;
+
+    if parsed.subsec_nanos() != 0 {
+        return Err(format!("Expected whole seconds: {}", duration));
+    }
+
+    Ok(parsed)
+}
+
+// Inverse of parse, using the largest unit that represents the duration
+// exactly.
+pub fn format(duration: &Duration) -> String {
+    const UNITS: [(&str, u128); 5] = [
+        ("d", 24 * 60 * 60 * 1000),
+        ("h", 60 * 60 * 1000),
+        ("m", 60 * 1000),
+        ("s", 1000),
+        ("ms", 1),
+    ];
+    let millis = duration.as_millis();
+
+    for (unit, size) in UNITS {
+        if millis != 0 && millis % size == 0 {
+            return format!("{}{}", millis / size, unit);
+        }
+    }
+
+    "0s".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse("500ms").unwrap(), Duration::from_millis(500));
+        assert_eq!(parse("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse("2d").unwrap(), Duration::from_secs(172800));
+    }
+
+    #[test]
+    fn test_format() {
+        for d in ["500ms", "30s", "90m", "2d", "0s"] {
+            assert_eq!(format(&parse(d).unwrap()), d);
+        }
+    }
+
+    #[test]
+    fn test_parse_secs() {
+        assert_eq!(parse_secs("1m").unwrap(), Duration::from_secs(60));
+        assert!(parse_secs("500ms").is_err());
+        assert!(parse_secs("1s500ms").is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("").is_err());
+        assert!(parse("s").is_err());
+        assert!(parse("10y").is_err());
+        assert!(parse("1h-30m").is_err());
+        assert!(parse("5000000000s").is_ok());
+        assert!(parse("18446744073709551615d").is_err());
+        assert!(parse("18446744073709551615s1s").is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod cli;
+pub mod config;
+pub mod denver;
+pub mod docker;
+pub mod duration;
@@ -1,14 +1,9 @@
 use clap::Parser;
 
-mod cli;
-use cli::Cli;
-mod config;
-mod denver;
-mod docker;
+use denver::cli::Cli;
 
-#[tokio::main]
-async fn main() {
+fn main() {
     let cli = Cli::parse();
 
-    denver::run(cli).await;
+    denver::denver::run(cli);
 }
@@ -0,0 +1,238 @@
+use denver::cli::Common;
+use denver::config::Config;
+use denver::docker::{ContainerEngine, DockerClient, DockerError, HASH_LABEL};
+use serde_json::Value;
+
+use self::mock::MockEngine;
+
+mod mock;
+
+const ENGINES: [&str; 2] = ["docker", "podman"];
+// The image tagged app in the fixtures
+const IMAGE: &str = "9a3c1f4e5b6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f607";
+
+fn client(mock: &MockEngine, engine: &str) -> DockerClient {
+    let config = format!(
+        r#"
+socket: unix://{}
+engine: {}
+"#,
+        mock.socket.display(),
+        engine
+    );
+
+    DockerClient::new(&Config::new(&config).unwrap()).unwrap()
+}
+
+#[tokio::test]
+async fn test_ping() {
+    for engine in ENGINES {
+        let mock = MockEngine::start(engine);
+        assert!(client(&mock, engine).ping().await.is_ok(), "{}", engine);
+    }
+}
+
+#[tokio::test]
+async fn test_list_containers() {
+    for engine in ENGINES {
+        let mock = MockEngine::start(engine);
+        let containers = client(&mock, engine).list_containers(false).await.unwrap();
+
+        let names: Vec<&str> = containers.iter().map(|c| c.names[0].as_str()).collect();
+        assert_eq!(names, vec!["/app"], "{}", engine);
+
+        let requests = mock.requests("GET", "/containers/json");
+        assert_eq!(
+            requests[0].query.contains("filters"),
+            engine == "docker",
+            "{}",
+            engine
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_build_image() {
+    let context = std::env::temp_dir().join(format!("denver-build-{}", std::process::id()));
+    std::fs::create_dir_all(&context).unwrap();
+    std::fs::write(context.join("Dockerfile"), "FROM fedora:36\n").unwrap();
+
+    let args = Common {
+        container: "app".to_string(),
+        no_cache: false,
+    };
+    let container = |tag: &str| {
+        let config = format!(
+            "containers:\n  app:\n    build: {{context: {}}}\n    run: {{workspace: /}}\n    tag: {}",
+            context.display(),
+            tag
+        );
+        Config::new(&config)
+            .unwrap()
+            .containers
+            .remove("app")
+            .unwrap()
+    };
+
+    for engine in ENGINES {
+        let mock = MockEngine::start(engine);
+        let docker = client(&mock, engine);
+
+        let result = docker.build_image(&args, &container("app")).await;
+        assert!(result.is_ok(), "{}", engine);
+
+        match docker.build_image(&args, &container("broken")).await {
+            Err(DockerError::Build(e)) => assert!(e.contains("manifest unknown"), "{}", e),
+            _ => panic!("{}: broken build did not fail", engine),
+        }
+    }
+
+    std::fs::remove_dir_all(&context).unwrap();
+}
+
+#[tokio::test]
+async fn test_create_auto_remove() {
+    for (engine, auto_remove) in [("docker", true), ("podman", false)] {
+        let mock = MockEngine::start(engine);
+        let docker = client(&mock, engine);
+        let config = Config::new(
+            "containers:\n  app:\n    build: {context: ctx/}\n    run:\n        args: {interactive: true, auto_remove: true}\n        workspace: /\n    tag: app",
+        )
+        .unwrap();
+
+        docker
+            .create_container("app", &config.containers["app"], None)
+            .await
+            .unwrap();
+
+        let requests = mock.requests("POST", "/containers/create");
+        let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+        assert_eq!(
+            body["HostConfig"]["AutoRemove"].as_bool().unwrap_or(false),
+            auto_remove,
+            "{}",
+            engine
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_create_labels() {
+    let mock = MockEngine::start("docker");
+    let docker = client(&mock, "docker");
+    let config = Config::new(
+        "containers:\n  app:\n    build: {context: ctx/}\n    run: {workspace: /, stop_signal: SIGINT, stop_timeout: 1m}\n    tag: app",
+    )
+    .unwrap();
+    let container = &config.containers["app"];
+
+    docker
+        .create_container("app", container, None)
+        .await
+        .unwrap();
+
+    let requests = mock.requests("POST", "/containers/create");
+    let body: Value = serde_json::from_slice(&requests[0].body).unwrap();
+    assert_eq!(body["Labels"]["manager"], "denver");
+    assert_eq!(body["StopSignal"], "SIGINT");
+    assert_eq!(body["StopTimeout"], 60);
+    assert_eq!(
+        body["Labels"][HASH_LABEL],
+        container.hash(IMAGE, None).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_image_id() {
+    for engine in ENGINES {
+        let mock = MockEngine::start(engine);
+        let docker = client(&mock, engine);
+
+        let id = docker.image_id("app").await.unwrap();
+        assert_eq!(id.as_deref(), Some(IMAGE), "{}", engine);
+    }
+}
+
+#[tokio::test]
+async fn test_usage() {
+    const ID: &str = "5d1b7f5cf3a1a0e9c4bd0c2d1b4b8fd4e0f3a8b9c6d7e2f1a0b9c8d7e6f5a4b3";
+    let mock = MockEngine::start("docker");
+
+    let usage = client(&mock, "docker").usage(ID).await.unwrap();
+
+    // A tenth of the host's time is 40% of a core on its 4 CPUs
+    assert!((usage.cpu - 40.0).abs() < 1e-9, "{}", usage.cpu);
+    assert_eq!(usage.memory, 64 * 1024 * 1024);
+}
+
+#[tokio::test]
+async fn test_remove_container() {
+    const ID: &str = "5d1b7f5cf3a1a0e9c4bd0c2d1b4b8fd4e0f3a8b9c6d7e2f1a0b9c8d7e6f5a4b3";
+
+    for engine in ENGINES {
+        let mock = MockEngine::start(engine);
+        let docker = client(&mock, engine);
+
+        docker.list_containers(true).await.unwrap();
+        let requests = mock.requests("GET", "/containers/json");
+        assert!(requests[0].query.contains("all=true"), "{}", engine);
+
+        docker
+            .remove_container(&ID.to_string(), false, true)
+            .await
+            .unwrap();
+        let requests = mock.requests("DELETE", &format!("/containers/{}", ID));
+        assert!(requests[0].query.contains("v=true"), "{}", engine);
+    }
+}
+
+// Replaces an engine's fixtures with what a real one responds, e.g.
+//   DENVER_RECORD=docker:/var/run/docker.sock cargo test --test docker -- --ignored
+// The base image has to be pulled beforehand. IDs and values the other tests
+// expect need updating to the recorded ones afterwards.
+#[tokio::test]
+#[ignore]
+async fn record_fixtures() {
+    let record = std::env::var("DENVER_RECORD").expect("DENVER_RECORD=<engine>:<socket>");
+    let (engine, upstream) = record.split_once(':').unwrap();
+    let base = std::env::var("DENVER_RECORD_IMAGE").unwrap_or_else(|_| "fedora:36".to_string());
+
+    let dir = std::env::temp_dir().join(format!("denver-record-{}", std::process::id()));
+    for (tag, from) in [
+        ("app", base.as_str()),
+        ("broken", "quay.io/org/missing:tag"),
+    ] {
+        std::fs::create_dir_all(dir.join(tag)).unwrap();
+        std::fs::write(dir.join(tag).join("Dockerfile"), format!("FROM {}\n", from)).unwrap();
+    }
+
+    let config = Config::new(&format!(
+        "containers:\n  app:\n    build: {{context: {0}/app}}\n    run: {{workspace: /}}\n    tag: app\n  broken:\n    build: {{context: {0}/broken}}\n    run: {{workspace: /}}\n    tag: broken",
+        dir.display()
+    ))
+    .unwrap();
+    let args = Common {
+        container: "app".to_string(),
+        no_cache: false,
+    };
+
+    let mock = MockEngine::record(engine, upstream.into());
+    let docker = client(&mock, engine);
+    let app = &config.containers["app"];
+
+    docker.ping().await.unwrap();
+    docker.build_image(&args, app).await.unwrap();
+    assert!(docker
+        .build_image(&args, &config.containers["broken"])
+        .await
+        .is_err());
+    docker.image_id("app").await.unwrap();
+
+    let id = docker.create_container("app", app, None).await.unwrap();
+    docker.run_container(&id).await.unwrap();
+    docker.list_containers(false).await.unwrap();
+    let _ = docker.usage(&id).await;
+    docker.remove_container(&id, true, false).await.unwrap();
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,410 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+// A canned response of a container engine
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct Fixture {
+    // Method and path, `*` matches any single path segment
+    request: String,
+    // Only used when the query string contains this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+    #[serde(default = "default_status")]
+    status: u16,
+    // Streamed responses send each item in the body on its own chunk
+    #[serde(default, skip_serializing_if = "is_false")]
+    stream: bool,
+    #[serde(default, skip_serializing_if = "Value::is_null")]
+    body: Value,
+}
+
+fn default_status() -> u16 {
+    200
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Fixture {
+    fn matches(&self, request: &Request) -> bool {
+        let (method, path) = self.request.split_once(' ').unwrap();
+        let segments = path.split('/').collect::<Vec<_>>();
+        let requested = request.path.split('/').collect::<Vec<_>>();
+
+        method == request.method
+            && segments.len() == requested.len()
+            && segments
+                .iter()
+                .zip(&requested)
+                .all(|(s, r)| *s == "*" || s == r)
+            && self
+                .query
+                .as_ref()
+                .map_or(true, |q| request.query.contains(q.as_str()))
+    }
+
+    fn encode(value: &Value) -> String {
+        match value {
+            Value::String(s) => s.clone(),
+            value => serde_json::to_string(value).unwrap(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub body: Vec<u8>,
+}
+
+// Serves canned responses of an engine's API over a unix socket, keeping
+// track of the requests it receives.
+pub struct MockEngine {
+    pub socket: PathBuf,
+    requests: Arc<Mutex<Vec<Request>>>,
+    // Where responses from a real engine are saved to, when recording
+    recording: Option<(PathBuf, Arc<Mutex<Vec<Fixture>>>)>,
+}
+
+impl MockEngine {
+    pub fn start(engine: &str) -> Self {
+        let fixtures: Vec<Fixture> =
+            serde_yaml::from_str(&std::fs::read_to_string(fixtures_path(engine)).unwrap()).unwrap();
+        let fixtures = Arc::new(fixtures);
+
+        let (socket, listener) = listen(engine);
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, fixtures.clone(), recorded.clone()));
+            }
+        });
+
+        MockEngine {
+            socket,
+            requests,
+            recording: None,
+        }
+    }
+
+    // Forwards every request to the engine listening on upstream, replacing
+    // the engine's fixtures with its responses once dropped
+    pub fn record(engine: &str, upstream: PathBuf) -> Self {
+        let (socket, listener) = listen(engine);
+        let requests = Arc::new(Mutex::new(vec![]));
+        let fixtures = Arc::new(Mutex::new(vec![]));
+        let (recorded, saved) = (requests.clone(), fixtures.clone());
+
+        tokio::spawn(async move {
+            // One at a time, so fixtures are saved in the order requests
+            // were made
+            while let Ok((stream, _)) = listener.accept().await {
+                forward(stream, &upstream, recorded.clone(), saved.clone()).await;
+            }
+        });
+
+        MockEngine {
+            socket,
+            requests,
+            recording: Some((fixtures_path(engine), fixtures)),
+        }
+    }
+
+    pub fn requests(&self, method: &str, path: &str) -> Vec<Request> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.method == method && r.path == path)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Drop for MockEngine {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.socket);
+
+        if let Some((path, fixtures)) = &self.recording {
+            let fixtures = serde_yaml::to_string(&*fixtures.lock().unwrap()).unwrap();
+            let header = "# Recorded by the record_fixtures test in tests/docker.rs\n";
+            std::fs::write(path, format!("{}{}", header, fixtures)).unwrap();
+        }
+    }
+}
+
+fn fixtures_path(engine: &str) -> PathBuf {
+    PathBuf::from(format!(
+        "{}/tests/fixtures/{}.yml",
+        env!("CARGO_MANIFEST_DIR"),
+        engine
+    ))
+}
+
+fn listen(engine: &str) -> (PathBuf, UnixListener) {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let socket = std::env::temp_dir().join(format!(
+        "denver-{}-{}-{}.sock",
+        engine,
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket).unwrap();
+
+    (socket, listener)
+}
+
+// Reads a request, along with its headers other than Connection
+async fn read_request(stream: &mut BufReader<UnixStream>) -> Option<(Request, Vec<String>)> {
+    let mut line = String::new();
+    if stream.read_line(&mut line).await.unwrap_or(0) == 0 {
+        return None;
+    }
+
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut length = 0;
+    let mut headers = vec![];
+    loop {
+        let mut header = String::new();
+        stream.read_line(&mut header).await.unwrap();
+
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap();
+            }
+
+            if !name.eq_ignore_ascii_case("connection") {
+                headers.push(header.to_string());
+            }
+        }
+    }
+
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body).await.unwrap();
+
+    let request = Request {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        body,
+    };
+
+    Some((request, headers))
+}
+
+// Handles a single request per connection, closing it afterwards
+async fn serve(
+    stream: UnixStream,
+    fixtures: Arc<Vec<Fixture>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+) {
+    let mut stream = BufReader::new(stream);
+
+    let request = match read_request(&mut stream).await {
+        Some((request, _)) => request,
+        None => return,
+    };
+
+    let response = match fixtures.iter().find(|f| f.matches(&request)) {
+        Some(fixture) => respond(fixture),
+        None => {
+            let body = format!(
+                r#"{{"message":"no fixture for {} {}"}}"#,
+                request.method, request.path
+            );
+            format!(
+                "HTTP/1.1 404 Not Found\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+    };
+
+    requests.lock().unwrap().push(request);
+
+    let stream = stream.get_mut();
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// Sends a request on to a real engine and saves its response as a fixture.
+// The client gets the response as the fixture would replay it.
+async fn forward(
+    stream: UnixStream,
+    upstream: &Path,
+    requests: Arc<Mutex<Vec<Request>>>,
+    fixtures: Arc<Mutex<Vec<Fixture>>>,
+) {
+    // Endless streams, like stats, are cut short after this long
+    static STREAM_LIMIT: Duration = Duration::from_secs(5);
+
+    let mut stream = BufReader::new(stream);
+    let (request, headers) = match read_request(&mut stream).await {
+        Some(request) => request,
+        None => return,
+    };
+
+    // HTTP/1.0 keeps the response unchunked, ending when the engine closes
+    // the connection
+    let target = match request.query.as_str() {
+        "" => request.path.clone(),
+        query => format!("{}?{}", request.path, query),
+    };
+    let mut forwarded = format!("{} {} HTTP/1.0\r\n", request.method, target).into_bytes();
+    for header in headers {
+        forwarded.extend(format!("{}\r\n", header).as_bytes());
+    }
+    forwarded.extend(b"Connection: close\r\n\r\n");
+    forwarded.extend(&request.body);
+
+    let mut engine = UnixStream::connect(upstream).await.unwrap();
+    engine.write_all(&forwarded).await.unwrap();
+
+    let mut response = vec![];
+    let _ = tokio::time::timeout(STREAM_LIMIT, engine.read_to_end(&mut response)).await;
+
+    let fixture = record(&request, &response);
+    let reply = respond(&fixture);
+
+    requests.lock().unwrap().push(request);
+    {
+        let mut fixtures = fixtures.lock().unwrap();
+        if !fixtures
+            .iter()
+            .any(|f| f.request == fixture.request && f.query == fixture.query)
+        {
+            fixtures.push(fixture);
+        }
+    }
+
+    let stream = stream.get_mut();
+    let _ = stream.write_all(reply.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// Turns a response into a fixture. IDs in the path match anything, so the
+// fixture serves requests for other containers and images as well.
+fn record(request: &Request, response: &[u8]) -> Fixture {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .unwrap_or(response.len());
+    let head = String::from_utf8_lossy(&response[..split]);
+    let mut body = response.get(split + 4..).unwrap_or_default().to_vec();
+    if head.to_lowercase().contains("transfer-encoding: chunked") {
+        body = dechunk(&body);
+    }
+    let body = String::from_utf8_lossy(&body);
+
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(500);
+
+    let path = request
+        .path
+        .split('/')
+        .map(|segment| {
+            let id = segment.len() >= 12 && segment.chars().all(|c| c.is_ascii_hexdigit());
+            if id {
+                "*"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let json = |text: &str| serde_json::from_str::<serde_json::Value>(text).ok();
+    let lines: Vec<&str> = body.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    let (stream, body) = if body.trim().is_empty() {
+        (false, Value::Null)
+    } else if let Some(value) = json(&body) {
+        (false, serde_yaml::to_value(value).unwrap())
+    } else if let Some(items) = lines.iter().map(|l| json(l)).collect::<Option<Vec<_>>>() {
+        (true, serde_yaml::to_value(items).unwrap())
+    } else {
+        (false, Value::String(body.to_string()))
+    };
+
+    Fixture {
+        request: format!("{} {}", request.method, path),
+        query: Some(request.query.clone()).filter(|q| !q.is_empty()),
+        status,
+        stream,
+        body,
+    }
+}
+
+fn dechunk(mut chunked: &[u8]) -> Vec<u8> {
+    let mut body = vec![];
+
+    while let Some(end) = chunked.windows(2).position(|w| w == b"\r\n") {
+        let size = String::from_utf8_lossy(&chunked[..end]);
+        let size = usize::from_str_radix(size.trim(), 16).unwrap_or(0);
+        if size == 0 {
+            break;
+        }
+
+        let start = end + 2;
+        body.extend(&chunked[start..(start + size).min(chunked.len())]);
+        chunked = chunked.get(start + size + 2..).unwrap_or_default();
+    }
+
+    body
+}
+
+fn respond(fixture: &Fixture) -> String {
+    let status = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nConnection: close\r\n",
+        fixture.status
+    );
+
+    match (&fixture.body, fixture.stream) {
+        (Value::Sequence(items), true) => {
+            let chunks: String = items
+                .iter()
+                .map(|item| {
+                    let chunk = format!("{}\n", Fixture::encode(item));
+                    format!("{:x}\r\n{}\r\n", chunk.len(), chunk)
+                })
+                .collect();
+
+            format!(
+                "{}Transfer-Encoding: chunked\r\n\r\n{}0\r\n\r\n",
+                status, chunks
+            )
+        }
+        (Value::Null, _) => format!("{}Content-Length: 0\r\n\r\n", status),
+        (body, _) => {
+            let body = Fixture::encode(body);
+            format!("{}Content-Length: {}\r\n\r\n{}", status, body.len(), body)
+        }
+    }
+}